@@ -0,0 +1,90 @@
+//! Serves the system clipboard to neovim. Neovide registers itself as the clipboard provider so
+//! that the "+ and "* registers reach the machine the gui runs on, even when neovim itself runs
+//! headless or on a remote host.
+
+use rmpv::Value;
+
+use crate::clipboard;
+
+// The provider looks the channel up on every call so that it keeps working after reconnecting to
+// a remote session on a new channel. A g:clipboard set by the user is left alone. A session which
+// was already running has loaded its clipboard provider by now, so the provider is loaded again to
+// pick up the new g:clipboard.
+pub const CLIPBOARD_PROVIDER_SETUP: &str = "if !exists('g:clipboard') || get(g:clipboard, 'name', '') ==# 'neovide' | let g:clipboard = {'name': 'neovide', 'copy': {'+': {lines, regtype -> rpcrequest(g:neovide_channel_id, 'neovide.set_clipboard', lines, regtype)}, '*': {lines, regtype -> rpcrequest(g:neovide_channel_id, 'neovide.set_clipboard', lines, regtype)}}, 'paste': {'+': {-> rpcrequest(g:neovide_channel_id, 'neovide.get_clipboard')}, '*': {-> rpcrequest(g:neovide_channel_id, 'neovide.get_clipboard')}}, 'cache_enabled': 0} | unlet! g:loaded_clipboard_provider | runtime autoload/provider/clipboard.vim | endif";
+
+// Neovim treats a trailing empty line as a linewise paste, which is exactly what splitting text
+// ending in a newline produces.
+fn text_to_lines(text: &str) -> Vec<Value> {
+    text.split('\n')
+        .map(|line| Value::from(line.strip_suffix('\r').unwrap_or(line)))
+        .collect()
+}
+
+fn lines_to_text(lines: &[Value], regtype: &str) -> Result<String, String> {
+    let mut lines = lines
+        .iter()
+        .map(|line| {
+            line.as_str()
+                .map(str::to_owned)
+                .ok_or_else(|| format!("Clipboard line is not a string: {}", line))
+        })
+        .collect::<Result<Vec<String>, String>>()?;
+
+    if regtype == "V" {
+        lines.push(String::new());
+    }
+
+    Ok(lines.join("\n"))
+}
+
+pub fn get_clipboard() -> Result<Value, Value> {
+    clipboard::get_contents()
+        .map(|contents| Value::Array(text_to_lines(&contents)))
+        .map_err(|error| Value::from(format!("Could not read the clipboard: {}", error)))
+}
+
+pub fn set_clipboard(arguments: Vec<Value>) -> Result<Value, Value> {
+    let lines = match arguments.get(0) {
+        Some(Value::Array(lines)) => lines,
+        _ => return Err(Value::from("Expected the lines to copy")),
+    };
+    let regtype = arguments.get(1).and_then(Value::as_str).unwrap_or("v");
+
+    let text = lines_to_text(lines, regtype).map_err(Value::from)?;
+    clipboard::set_contents(text)
+        .map(|_| Value::Nil)
+        .map_err(|error| Value::from(format!("Could not write the clipboard: {}", error)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_to_lines() {
+        assert_eq!(
+            text_to_lines("first\r\nsecond"),
+            vec![Value::from("first"), Value::from("second")]
+        );
+        assert_eq!(
+            text_to_lines("line\n"),
+            vec![Value::from("line"), Value::from("")]
+        );
+    }
+
+    #[test]
+    fn test_provider_setup_reloads_the_provider() {
+        let reload = "unlet! g:loaded_clipboard_provider | runtime autoload/provider/clipboard.vim";
+        let assignment = CLIPBOARD_PROVIDER_SETUP.find("let g:clipboard").unwrap();
+        let reloaded = CLIPBOARD_PROVIDER_SETUP.find(reload).unwrap();
+        assert!(assignment < reloaded);
+    }
+
+    #[test]
+    fn test_lines_to_text() {
+        let lines = vec![Value::from("first"), Value::from("second")];
+        assert_eq!(lines_to_text(&lines, "v").unwrap(), "first\nsecond");
+        assert_eq!(lines_to_text(&lines, "V").unwrap(), "first\nsecond\n");
+        assert!(lines_to_text(&[Value::from(1)], "v").is_err());
+    }
+}
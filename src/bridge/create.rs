@@ -6,6 +6,9 @@ use std::{
     process::Stdio,
 };
 
+#[cfg(unix)]
+use std::path::Path;
+
 use tokio::{
     io::split,
     net::{TcpStream, ToSocketAddrs},
@@ -14,6 +17,9 @@ use tokio::{
     task::JoinHandle,
 };
 
+#[cfg(unix)]
+use tokio::net::UnixStream;
+
 use nvim_rs::compat::tokio::TokioAsyncReadCompatExt;
 use nvim_rs::{error::LoopError, neovim::Neovim, Handler};
 
@@ -36,6 +42,25 @@ where
     Ok((neovim, io_handle))
 }
 
+/// Connect to a neovim instance via a unix domain socket such as the one created by
+/// `nvim --listen /tmp/nvim.sock`
+#[cfg(unix)]
+pub async fn new_unix<P, H>(
+    path: P,
+    handler: H,
+) -> io::Result<(Neovim<TxWrapper>, JoinHandle<Result<(), Box<LoopError>>>)>
+where
+    P: AsRef<Path>,
+    H: Handler<Writer = TxWrapper>,
+{
+    let stream = UnixStream::connect(path).await?;
+    let (reader, writer) = split(stream);
+    let (neovim, io) = Neovim::<TxWrapper>::new(reader.compat_read(), writer.wrap_tx(), handler);
+    let io_handle = spawn(io);
+
+    Ok((neovim, io_handle))
+}
+
 /// Connect to a neovim instance by spawning a new one
 ///
 /// stdin/stdout will be rewritten to `Stdio::piped()`
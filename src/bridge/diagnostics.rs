@@ -0,0 +1,51 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use log::{error, warn};
+use parking_lot::Mutex;
+
+use super::events::ParseError;
+
+lazy_static! {
+    pub static ref DIAGNOSTICS: Diagnostics = Diagnostics::new();
+}
+
+// Keeps track of redraw events which had to be dropped so that problems with newer or unusual
+// neovim versions show up somewhere other than the log.
+pub struct Diagnostics {
+    unknown_events: AtomicU64,
+    invalid_events: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Diagnostics {
+        Diagnostics {
+            unknown_events: AtomicU64::new(0),
+            invalid_events: AtomicU64::new(0),
+            last_error: Mutex::new(None),
+        }
+    }
+
+    pub fn record_dropped_event(&self, error: &ParseError) {
+        if let ParseError::UnknownEvent(_) = error {
+            warn!("Dropped redraw event: {}", error);
+            self.unknown_events.fetch_add(1, Ordering::Relaxed);
+        } else {
+            error!("Dropped redraw event: {}", error);
+            self.invalid_events.fetch_add(1, Ordering::Relaxed);
+        }
+        *self.last_error.lock() = Some(error.to_string());
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Neovide diagnostics\nUnknown redraw events dropped: {}\nMalformed redraw event batches dropped: {}\n",
+            self.unknown_events.load(Ordering::Relaxed),
+            self.invalid_events.load(Ordering::Relaxed),
+        );
+        if let Some(last_error) = self.last_error.lock().as_ref() {
+            summary.push_str(&format!("Last error: {}\n", last_error));
+        }
+        summary
+    }
+}
@@ -2,6 +2,7 @@ use std::convert::TryInto;
 use std::error;
 use std::fmt;
 
+use log::warn;
 use rmpv::Value;
 use skulpin::skia_safe::Color4f;
 
@@ -17,7 +18,14 @@ pub enum ParseError {
     InvalidF64(Value),
     InvalidBool(Value),
     InvalidWindowAnchor(Value),
+    InvalidHandle(Value),
     InvalidFormat,
+    UnknownEvent(String),
+    InvalidEvent {
+        event_name: String,
+        index: usize,
+        error: Box<ParseError>,
+    },
 }
 type Result<T> = std::result::Result<T, ParseError>;
 
@@ -34,7 +42,18 @@ impl fmt::Display for ParseError {
             ParseError::InvalidWindowAnchor(value) => {
                 write!(f, "invalid window anchor format {}", value)
             }
+            ParseError::InvalidHandle(value) => write!(f, "invalid handle format {}", value),
             ParseError::InvalidFormat => write!(f, "invalid event format"),
+            ParseError::UnknownEvent(event_name) => write!(f, "unknown event {}", event_name),
+            ParseError::InvalidEvent {
+                event_name,
+                index,
+                error,
+            } => write!(
+                f,
+                "could not parse arguments {} of {} event: {}",
+                index, event_name, error
+            ),
         }
     }
 }
@@ -54,7 +73,21 @@ pub struct GridLineCell {
 
 pub type StyledContent = Vec<(u64, String)>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct TabInfo {
+    pub handle: u64,
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct PopupMenuItem {
+    pub word: String,
+    pub kind: String,
+    pub menu: String,
+    pub info: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum MessageKind {
     Unknown,
     Confirm,
@@ -160,6 +193,10 @@ pub enum RedrawEvent {
         id: u64,
         style: Style,
     },
+    HighlightGroupSet {
+        name: String,
+        id: u64,
+    },
     GridLine {
         grid: u64,
         row: u64,
@@ -188,6 +225,7 @@ pub enum RedrawEvent {
     },
     WindowPosition {
         grid: u64,
+        window: u64,
         start_row: u64,
         start_column: u64,
         width: u64,
@@ -195,6 +233,7 @@ pub enum RedrawEvent {
     },
     WindowFloatPosition {
         grid: u64,
+        window: u64,
         anchor: WindowAnchor,
         anchor_grid: u64,
         anchor_row: f64,
@@ -203,6 +242,7 @@ pub enum RedrawEvent {
     },
     WindowExternalPosition {
         grid: u64,
+        window: u64,
     },
     WindowHide {
         grid: u64,
@@ -266,6 +306,30 @@ pub enum RedrawEvent {
     MessageHistoryShow {
         entries: Vec<(MessageKind, StyledContent)>,
     },
+    PopupMenuShow {
+        items: Vec<PopupMenuItem>,
+        selected: Option<u64>,
+        row: u64,
+        column: u64,
+        grid: Option<u64>,
+    },
+    PopupMenuSelect {
+        selected: Option<u64>,
+    },
+    PopupMenuHide,
+    TablineUpdate {
+        current_tab: u64,
+        tabs: Vec<TabInfo>,
+    },
+    // Not sent by neovim. The bridge queues this right before attaching the ui again after a
+    // detach so that all grid state is rebuilt from the full redraw neovim sends on attach.
+    UiReattach,
+    // Not sent by neovim either. Queued when a remote connection drops and the bridge starts
+    // trying to reconnect.
+    ConnectionLost,
+    // Not sent by neovim either. Queued after a neovide setting variable changes, since some of
+    // them change how the grids are drawn.
+    SettingsChanged,
 }
 
 #[derive(Debug)]
@@ -393,6 +457,17 @@ fn parse_bool(bool_value: Value) -> Result<bool> {
     bool_value.try_into().map_err(ParseError::InvalidBool)
 }
 
+// Buffers, windows and tabpages are sent as msgpack extension types wrapping their handle.
+fn parse_handle(handle_value: Value) -> Result<u64> {
+    if let Value::Ext(_, data) = &handle_value {
+        if let Ok(handle) = rmpv::decode::read_value(&mut data.as_slice()) {
+            return parse_u64(handle);
+        }
+    }
+
+    Err(ParseError::InvalidHandle(handle_value))
+}
+
 fn parse_set_title(set_title_arguments: Vec<Value>) -> Result<RedrawEvent> {
     let [title] = extract_values(set_title_arguments, [Value::Nil])?;
 
@@ -512,29 +587,31 @@ fn parse_style(style_map: Value) -> Result<Style> {
 
     for attribute in attributes {
         if let (Value::String(name), value) = attribute {
-            match (name.as_str().unwrap(), value) {
-                ("foreground", Value::Integer(packed_color)) => {
-                    style.colors.foreground = Some(unpack_color(packed_color.as_u64().unwrap()))
+            match (name.as_str(), value) {
+                (Some("foreground"), packed_color @ Value::Integer(_)) => {
+                    style.colors.foreground = Some(unpack_color(parse_u64(packed_color)?))
                 }
-                ("background", Value::Integer(packed_color)) => {
-                    style.colors.background = Some(unpack_color(packed_color.as_u64().unwrap()))
+                (Some("background"), packed_color @ Value::Integer(_)) => {
+                    style.colors.background = Some(unpack_color(parse_u64(packed_color)?))
                 }
-                ("special", Value::Integer(packed_color)) => {
-                    style.colors.special = Some(unpack_color(packed_color.as_u64().unwrap()))
+                (Some("special"), packed_color @ Value::Integer(_)) => {
+                    style.colors.special = Some(unpack_color(parse_u64(packed_color)?))
                 }
-                ("reverse", Value::Boolean(reverse)) => style.reverse = reverse,
-                ("italic", Value::Boolean(italic)) => style.italic = italic,
-                ("bold", Value::Boolean(bold)) => style.bold = bold,
-                ("strikethrough", Value::Boolean(strikethrough)) => {
+                (Some("reverse"), Value::Boolean(reverse)) => style.reverse = reverse,
+                (Some("italic"), Value::Boolean(italic)) => style.italic = italic,
+                (Some("bold"), Value::Boolean(bold)) => style.bold = bold,
+                (Some("strikethrough"), Value::Boolean(strikethrough)) => {
                     style.strikethrough = strikethrough
                 }
-                ("underline", Value::Boolean(underline)) => style.underline = underline,
-                ("undercurl", Value::Boolean(undercurl)) => style.undercurl = undercurl,
-                ("blend", Value::Integer(blend)) => style.blend = blend.as_u64().unwrap() as u8,
-                _ => println!("Ignored style attribute: {}", name),
+                (Some("underline"), Value::Boolean(underline)) => style.underline = underline,
+                (Some("undercurl"), Value::Boolean(undercurl)) => style.undercurl = undercurl,
+                (Some("blend"), blend @ Value::Integer(_)) => {
+                    style.blend = parse_u64(blend)?.min(100) as u8
+                }
+                _ => warn!("Ignored style attribute: {}", name),
             }
         } else {
-            println!("Invalid attribute format");
+            warn!("Invalid attribute format");
         }
     }
 
@@ -553,6 +630,15 @@ fn parse_hl_attr_define(hl_attr_define_arguments: Vec<Value>) -> Result<RedrawEv
     })
 }
 
+fn parse_hl_group_set(hl_group_set_arguments: Vec<Value>) -> Result<RedrawEvent> {
+    let [name, id] = extract_values(hl_group_set_arguments, [Value::Nil, Value::Nil])?;
+
+    Ok(RedrawEvent::HighlightGroupSet {
+        name: parse_string(name)?,
+        id: parse_u64(id)?,
+    })
+}
+
 fn parse_grid_line_cell(grid_line_cell: Value) -> Result<GridLineCell> {
     fn take_value(val: &mut Value) -> Value {
         std::mem::replace(val, Value::Nil)
@@ -657,11 +743,12 @@ fn parse_win_pos(win_pos_arguments: Vec<Value>) -> Result<RedrawEvent> {
         Value::Nil,
         Value::Nil,
     ];
-    let [grid, _window, start_row, start_column, width, height] =
+    let [grid, window, start_row, start_column, width, height] =
         extract_values(win_pos_arguments, values)?;
 
     Ok(RedrawEvent::WindowPosition {
         grid: parse_u64(grid)?,
+        window: parse_handle(window)?,
         start_row: parse_u64(start_row)?,
         start_column: parse_u64(start_column)?,
         width: parse_u64(width)?,
@@ -690,11 +777,12 @@ fn parse_win_float_pos(win_float_pos_arguments: Vec<Value>) -> Result<RedrawEven
         Value::Nil,
         Value::Nil,
     ];
-    let [grid, _window, anchor, anchor_grid, anchor_row, anchor_column, focusable] =
+    let [grid, window, anchor, anchor_grid, anchor_row, anchor_column, focusable] =
         extract_values(win_float_pos_arguments, values)?;
 
     Ok(RedrawEvent::WindowFloatPosition {
         grid: parse_u64(grid)?,
+        window: parse_handle(window)?,
         anchor: parse_window_anchor(anchor)?,
         anchor_grid: parse_u64(anchor_grid)?,
         anchor_row: parse_f64(anchor_row)?,
@@ -704,10 +792,11 @@ fn parse_win_float_pos(win_float_pos_arguments: Vec<Value>) -> Result<RedrawEven
 }
 
 fn parse_win_external_pos(win_external_pos_arguments: Vec<Value>) -> Result<RedrawEvent> {
-    let [grid, _window] = extract_values(win_external_pos_arguments, [Value::Nil, Value::Nil])?;
+    let [grid, window] = extract_values(win_external_pos_arguments, [Value::Nil, Value::Nil])?;
 
     Ok(RedrawEvent::WindowExternalPosition {
         grid: parse_u64(grid)?,
+        window: parse_handle(window)?,
     })
 }
 
@@ -889,6 +978,142 @@ fn parse_msg_history_show(msg_history_show_arguments: Vec<Value>) -> Result<Redr
     })
 }
 
+// Neovim uses -1 to signal that no item is selected or that the menu belongs to the cmdline.
+fn parse_optional_u64(optional_value: Value) -> Result<Option<u64>> {
+    let value = parse_i64(optional_value)?;
+
+    if value < 0 {
+        Ok(None)
+    } else {
+        Ok(Some(value as u64))
+    }
+}
+
+fn parse_popupmenu_item(item: Value) -> Result<PopupMenuItem> {
+    let values = [Value::Nil, Value::Nil, Value::Nil, Value::Nil];
+    let [word, kind, menu, info] = extract_values(parse_array(item)?, values)?;
+
+    Ok(PopupMenuItem {
+        word: parse_string(word)?,
+        kind: parse_string(kind)?,
+        menu: parse_string(menu)?,
+        info: parse_string(info)?,
+    })
+}
+
+fn parse_popupmenu_show(popupmenu_show_arguments: Vec<Value>) -> Result<RedrawEvent> {
+    let values = [Value::Nil, Value::Nil, Value::Nil, Value::Nil, Value::Nil];
+    let [items, selected, row, column, grid] = extract_values(popupmenu_show_arguments, values)?;
+
+    Ok(RedrawEvent::PopupMenuShow {
+        items: parse_array(items)?
+            .into_iter()
+            .map(parse_popupmenu_item)
+            .collect::<Result<_>>()?,
+        selected: parse_optional_u64(selected)?,
+        row: parse_u64(row)?,
+        column: parse_u64(column)?,
+        grid: parse_optional_u64(grid)?,
+    })
+}
+
+fn parse_popupmenu_select(popupmenu_select_arguments: Vec<Value>) -> Result<RedrawEvent> {
+    let [selected] = extract_values(popupmenu_select_arguments, [Value::Nil])?;
+
+    Ok(RedrawEvent::PopupMenuSelect {
+        selected: parse_optional_u64(selected)?,
+    })
+}
+
+fn parse_tab_info(tab_info_value: Value) -> Result<TabInfo> {
+    let mut handle = None;
+    let mut name = String::new();
+
+    for (key, value) in parse_map(tab_info_value)? {
+        match parse_string(key)?.as_str() {
+            "tab" => handle = Some(parse_handle(value)?),
+            "name" => name = parse_string(value)?,
+            _ => {}
+        }
+    }
+
+    Ok(TabInfo {
+        handle: handle.ok_or(ParseError::InvalidFormat)?,
+        name,
+    })
+}
+
+fn parse_tabline_update(tabline_update_arguments: Vec<Value>) -> Result<RedrawEvent> {
+    // Newer versions of neovim also send the current buffer and buffer list which are ignored
+    let mut arguments = tabline_update_arguments.into_iter();
+    let current_tab = arguments.next().ok_or(ParseError::InvalidFormat)?;
+    let tabs = arguments.next().ok_or(ParseError::InvalidFormat)?;
+
+    Ok(RedrawEvent::TablineUpdate {
+        current_tab: parse_handle(current_tab)?,
+        tabs: parse_array(tabs)?
+            .into_iter()
+            .map(parse_tab_info)
+            .collect::<Result<_>>()?,
+    })
+}
+
+fn parse_event(event_name: &str, event: Value) -> Result<Option<RedrawEvent>> {
+    let event_parameters = parse_array(event)?;
+    let possible_parsed_event = match event_name {
+        "set_title" => Some(parse_set_title(event_parameters)?),
+        // Events the gui has no use for yet
+        "set_icon" | "bell" | "visual_bell" | "update_menu" | "suspend" | "win_extmark"
+        | "msg_history_clear" | "chdir" => None,
+        "mode_info_set" => Some(parse_mode_info_set(event_parameters)?),
+        "option_set" => Some(parse_option_set(event_parameters)?),
+        "mode_change" => Some(parse_mode_change(event_parameters)?),
+        "mouse_on" => Some(RedrawEvent::MouseOn),
+        "mouse_off" => Some(RedrawEvent::MouseOff),
+        "busy_start" => Some(RedrawEvent::BusyStart),
+        "busy_stop" => Some(RedrawEvent::BusyStop),
+        "flush" => Some(RedrawEvent::Flush),
+        "grid_resize" => Some(parse_grid_resize(event_parameters)?),
+        "default_colors_set" => Some(parse_default_colors(event_parameters)?),
+        "hl_attr_define" => Some(parse_hl_attr_define(event_parameters)?),
+        "hl_group_set" => Some(parse_hl_group_set(event_parameters)?),
+        "grid_line" => Some(parse_grid_line(event_parameters)?),
+        "grid_clear" => Some(parse_grid_clear(event_parameters)?),
+        "grid_destroy" => Some(parse_grid_destroy(event_parameters)?),
+        "grid_cursor_goto" => Some(parse_grid_cursor_goto(event_parameters)?),
+        "grid_scroll" => Some(parse_grid_scroll(event_parameters)?),
+        "win_pos" => Some(parse_win_pos(event_parameters)?),
+        "win_float_pos" => Some(parse_win_float_pos(event_parameters)?),
+        "win_external_pos" => Some(parse_win_external_pos(event_parameters)?),
+        "win_hide" => Some(parse_win_hide(event_parameters)?),
+        "win_close" => Some(parse_win_close(event_parameters)?),
+        "msg_set_pos" => Some(parse_msg_set_pos(event_parameters)?),
+        "win_viewport" => Some(parse_win_viewport(event_parameters)?),
+        "cmdline_show" => Some(parse_cmdline_show(event_parameters)?),
+        "cmdline_pos" => Some(parse_cmdline_pos(event_parameters)?),
+        "cmdline_special_char" => Some(parse_cmdline_special_char(event_parameters)?),
+        "cmdline_hide" => Some(RedrawEvent::CommandLineHide),
+        "cmdline_block_show" => Some(parse_cmdline_block_show(event_parameters)?),
+        "cmdline_block_append" => Some(parse_cmdline_block_append(event_parameters)?),
+        "cmdline_block_hide" => Some(RedrawEvent::CommandLineBlockHide),
+        "msg_show" => Some(parse_msg_show(event_parameters)?),
+        "msg_clear" => Some(RedrawEvent::MessageClear),
+        "msg_showmode" => Some(parse_msg_showmode(event_parameters)?),
+        "msg_showcmd" => Some(parse_msg_showcmd(event_parameters)?),
+        "msg_ruler" => Some(parse_msg_ruler(event_parameters)?),
+        "msg_history_show" => Some(parse_msg_history_show(event_parameters)?),
+        "popupmenu_show" => Some(parse_popupmenu_show(event_parameters)?),
+        "popupmenu_select" => Some(parse_popupmenu_select(event_parameters)?),
+        "popupmenu_hide" => Some(RedrawEvent::PopupMenuHide),
+        "tabline_update" => Some(parse_tabline_update(event_parameters)?),
+        _ => return Err(ParseError::UnknownEvent(event_name.to_string())),
+    };
+
+    Ok(possible_parsed_event)
+}
+
+// Parses a batch of redraw events sharing the same name. The whole batch is rejected if any of
+// them is malformed, since applying only part of a batch could leave the grids inconsistent.
 pub fn parse_redraw_event(event_value: Value) -> Result<Vec<RedrawEvent>> {
     let mut event_contents = parse_array(event_value)?.into_iter();
     let event_name = event_contents
@@ -899,49 +1124,16 @@ pub fn parse_redraw_event(event_value: Value) -> Result<Vec<RedrawEvent>> {
     let events = event_contents;
     let mut parsed_events = Vec::with_capacity(events.len());
 
-    for event in events {
-        let event_parameters = parse_array(event)?;
-        let possible_parsed_event = match event_name.as_str() {
-            "set_title" => Some(parse_set_title(event_parameters)?),
-            "set_icon" => None, // Ignore set icon for now
-            "mode_info_set" => Some(parse_mode_info_set(event_parameters)?),
-            "option_set" => Some(parse_option_set(event_parameters)?),
-            "mode_change" => Some(parse_mode_change(event_parameters)?),
-            "mouse_on" => Some(RedrawEvent::MouseOn),
-            "mouse_off" => Some(RedrawEvent::MouseOff),
-            "busy_start" => Some(RedrawEvent::BusyStart),
-            "busy_stop" => Some(RedrawEvent::BusyStop),
-            "flush" => Some(RedrawEvent::Flush),
-            "grid_resize" => Some(parse_grid_resize(event_parameters)?),
-            "default_colors_set" => Some(parse_default_colors(event_parameters)?),
-            "hl_attr_define" => Some(parse_hl_attr_define(event_parameters)?),
-            "grid_line" => Some(parse_grid_line(event_parameters)?),
-            "grid_clear" => Some(parse_grid_clear(event_parameters)?),
-            "grid_destroy" => Some(parse_grid_destroy(event_parameters)?),
-            "grid_cursor_goto" => Some(parse_grid_cursor_goto(event_parameters)?),
-            "grid_scroll" => Some(parse_grid_scroll(event_parameters)?),
-            "win_pos" => Some(parse_win_pos(event_parameters)?),
-            "win_float_pos" => Some(parse_win_float_pos(event_parameters)?),
-            "win_external_pos" => Some(parse_win_external_pos(event_parameters)?),
-            "win_hide" => Some(parse_win_hide(event_parameters)?),
-            "win_close" => Some(parse_win_close(event_parameters)?),
-            "msg_set_pos" => Some(parse_msg_set_pos(event_parameters)?),
-            "win_viewport" => Some(parse_win_viewport(event_parameters)?),
-            "cmdline_show" => Some(parse_cmdline_show(event_parameters)?),
-            "cmdline_pos" => Some(parse_cmdline_pos(event_parameters)?),
-            "cmdline_special_char" => Some(parse_cmdline_special_char(event_parameters)?),
-            "cmdline_hide" => Some(RedrawEvent::CommandLineHide),
-            "cmdline_block_show" => Some(parse_cmdline_block_show(event_parameters)?),
-            "cmdline_block_append" => Some(parse_cmdline_block_append(event_parameters)?),
-            "cmdline_block_hide" => Some(RedrawEvent::CommandLineBlockHide),
-            "msg_show" => Some(parse_msg_show(event_parameters)?),
-            "msg_clear" => Some(RedrawEvent::MessageClear),
-            "msg_showmode" => Some(parse_msg_showmode(event_parameters)?),
-            "msg_showcmd" => Some(parse_msg_showcmd(event_parameters)?),
-            "msg_ruler" => Some(parse_msg_ruler(event_parameters)?),
-            "msg_history_show" => Some(parse_msg_history_show(event_parameters)?),
-            _ => None,
-        };
+    for (index, event) in events.enumerate() {
+        let possible_parsed_event =
+            parse_event(&event_name, event).map_err(|error| match error {
+                ParseError::UnknownEvent(_) => error,
+                error => ParseError::InvalidEvent {
+                    event_name: event_name.clone(),
+                    index,
+                    error: Box::new(error),
+                },
+            })?;
 
         if let Some(parsed_event) = possible_parsed_event {
             parsed_events.push(parsed_event);
@@ -977,16 +1169,16 @@ pub fn parse_client_version(version_value: Value) -> Result<ClientVersion> {
 
     for version_property in version_map {
         if let (Value::String(name), value) = version_property {
-            match (name.as_str().unwrap(), value) {
+            match (name.as_str().ok_or(ParseError::InvalidFormat)?, value) {
                 ("major", major) => version.major = parse_u64(major)?,
                 ("minor", minor) => version.minor = Some(parse_u64(minor)?),
                 ("patch", patch) => version.patch = Some(parse_u64(patch)?),
                 ("prerelease", prerelease) => version.prerelease = Some(parse_string(prerelease)?),
                 ("commit", commit) => version.commit = Some(parse_string(commit)?),
-                _ => println!("Ignored client version property: {}", name),
+                _ => warn!("Ignored client version property: {}", name),
             }
         } else {
-            println!("Invalid client version format");
+            warn!("Invalid client version format");
         }
     }
 
@@ -1011,14 +1203,14 @@ pub fn parse_client_info(client_info_value: Value) -> Result<ClientInfo> {
 
     for info_property in client_info_map {
         if let (Value::String(name), value) = info_property {
-            match (name.as_str().unwrap(), value) {
+            match (name.as_str().ok_or(ParseError::InvalidFormat)?, value) {
                 ("name", name) => client_info.name = parse_string(name)?,
                 ("version", version) => client_info.version = parse_client_version(version)?,
                 ("type", client_type) => client_info.client_type = parse_client_type(client_type)?,
-                _ => println!("Ignored client type property: {}", name),
+                _ => warn!("Ignored client type property: {}", name),
             }
         } else {
-            println!("Invalid client info format");
+            warn!("Invalid client info format");
         }
     }
 
@@ -1032,7 +1224,7 @@ pub fn parse_channel_info(channel_value: Value) -> Result<ChannelInfo> {
 
     for channel_property in channel_map {
         if let (Value::String(name), value) = channel_property {
-            match (name.as_str().unwrap(), value) {
+            match (name.as_str().ok_or(ParseError::InvalidFormat)?, value) {
                 ("id", channel_id) => channel_info.id = parse_u64(channel_id)?,
                 ("stream", stream) => channel_info.stream = parse_channel_stream_type(stream)?,
                 ("mode", mode) => channel_info.mode = parse_channel_mode(mode)?,
@@ -1041,10 +1233,10 @@ pub fn parse_channel_info(channel_value: Value) -> Result<ChannelInfo> {
                 ("client", client_info) => {
                     channel_info.client = Some(parse_client_info(client_info)?)
                 }
-                _ => println!("Ignored channel info property: {}", name),
+                _ => warn!("Ignored channel info property: {}", name),
             }
         } else {
-            println!("Invalid channel info format");
+            warn!("Invalid channel info format");
         }
     }
 
@@ -1057,3 +1249,51 @@ pub fn parse_channel_list(channel_infos: Vec<Value>) -> Result<Vec<ChannelInfo>>
         .map(parse_channel_info)
         .collect::<Result<Vec<ChannelInfo>>>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_batch(event_name: &str, arguments: Vec<Vec<Value>>) -> Value {
+        let mut batch = vec![Value::from(event_name)];
+        batch.extend(arguments.into_iter().map(Value::Array));
+        Value::Array(batch)
+    }
+
+    #[test]
+    fn test_parse_redraw_event() {
+        let batch = event_batch(
+            "grid_clear",
+            vec![vec![Value::from(1u64)], vec![Value::from(2u64)]],
+        );
+        assert_eq!(parse_redraw_event(batch).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_unknown_event() {
+        let batch = event_batch("some_future_event", vec![vec![]]);
+        match parse_redraw_event(batch) {
+            Err(ParseError::UnknownEvent(event_name)) => {
+                assert_eq!(event_name, "some_future_event")
+            }
+            result => panic!("Expected an unknown event error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_invalid_event_context() {
+        let batch = event_batch(
+            "grid_clear",
+            vec![vec![Value::from(1u64)], vec![Value::from("not a grid")]],
+        );
+        match parse_redraw_event(batch) {
+            Err(ParseError::InvalidEvent {
+                event_name, index, ..
+            }) => {
+                assert_eq!(event_name, "grid_clear");
+                assert_eq!(index, 1);
+            }
+            result => panic!("Expected an invalid event error, got {:?}", result),
+        }
+    }
+}
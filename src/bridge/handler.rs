@@ -2,22 +2,25 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use crossfire::mpsc::TxUnbounded;
-use log::trace;
+use log::{error, trace};
 use nvim_rs::{Handler, Neovim};
 use parking_lot::Mutex;
 use rmpv::Value;
 use tokio::task;
 
+use super::clipboard::{get_clipboard, set_clipboard};
+use super::diagnostics::DIAGNOSTICS;
 use super::events::{parse_redraw_event, RedrawEvent};
+use super::recording::{record_path, EventRecorder};
 use super::ui_commands::UiCommand;
 use crate::bridge::TxWrapper;
-use crate::error_handling::ResultPanicExplanation;
 use crate::settings::SETTINGS;
 
 #[derive(Clone)]
 pub struct NeovimHandler {
     ui_command_sender: Arc<Mutex<TxUnbounded<UiCommand>>>,
     redraw_event_sender: Arc<Mutex<TxUnbounded<RedrawEvent>>>,
+    recorder: Option<Arc<EventRecorder>>,
 }
 
 impl NeovimHandler {
@@ -25,9 +28,18 @@ impl NeovimHandler {
         ui_command_sender: TxUnbounded<UiCommand>,
         redraw_event_sender: TxUnbounded<RedrawEvent>,
     ) -> NeovimHandler {
+        let recorder = record_path().and_then(|path| match EventRecorder::new(&path) {
+            Ok(recorder) => Some(Arc::new(recorder)),
+            Err(error) => {
+                error!("Could not create recording {}: {}", path, error);
+                None
+            }
+        });
+
         NeovimHandler {
             ui_command_sender: Arc::new(Mutex::new(ui_command_sender)),
             redraw_event_sender: Arc::new(Mutex::new(redraw_event_sender)),
+            recorder,
         }
     }
 }
@@ -36,32 +48,72 @@ impl NeovimHandler {
 impl Handler for NeovimHandler {
     type Writer = TxWrapper;
 
-    async fn handle_notify(
+    async fn handle_request(
         &self,
         event_name: String,
         arguments: Vec<Value>,
         _neovim: Neovim<TxWrapper>,
+    ) -> Result<Value, Value> {
+        trace!("Neovim request: {:?}", &event_name);
+
+        // Clipboard access can block on the platform's clipboard owner
+        task::spawn_blocking(move || match event_name.as_ref() {
+            "neovide.get_clipboard" => get_clipboard(),
+            "neovide.set_clipboard" => set_clipboard(arguments),
+            _ => Err(Value::from(format!("Unknown request: {}", event_name))),
+        })
+        .await
+        .unwrap_or_else(|error| Err(Value::from(error.to_string())))
+    }
+
+    async fn handle_notify(
+        &self,
+        event_name: String,
+        arguments: Vec<Value>,
+        neovim: Neovim<TxWrapper>,
     ) {
         trace!("Neovim notification: {:?}", &event_name);
 
-        #[cfg(windows)]
+        // Waiting on a request from inside the handler would hold up the io loop that has to
+        // deliver its response
+        if event_name == "neovide.diagnostics" {
+            task::spawn(async move {
+                neovim.out_write(&DIAGNOSTICS.summary()).await.ok();
+            });
+            return;
+        }
+
+        if let Some(recorder) = &self.recorder {
+            if event_name == "redraw" {
+                recorder.record(&arguments);
+            }
+        }
+
         let ui_command_sender = self.ui_command_sender.clone();
 
         let redraw_event_sender = self.redraw_event_sender.clone();
         task::spawn_blocking(move || match event_name.as_ref() {
             "redraw" => {
                 for events in arguments {
-                    let parsed_events = parse_redraw_event(events)
-                        .unwrap_or_explained_panic("Could not parse event from neovim");
-
-                    for parsed_event in parsed_events {
-                        let redraw_event_sender = redraw_event_sender.lock();
-                        redraw_event_sender.send(parsed_event).ok();
+                    match parse_redraw_event(events) {
+                        Ok(parsed_events) => {
+                            for parsed_event in parsed_events {
+                                let redraw_event_sender = redraw_event_sender.lock();
+                                redraw_event_sender.send(parsed_event).ok();
+                            }
+                        }
+                        Err(error) => DIAGNOSTICS.record_dropped_event(&error),
                     }
                 }
             }
             "setting_changed" => {
                 SETTINGS.handle_changed_notification(arguments);
+                let redraw_event_sender = redraw_event_sender.lock();
+                redraw_event_sender.send(RedrawEvent::SettingsChanged).ok();
+            }
+            "neovide.detach" => {
+                let ui_command_sender = ui_command_sender.lock();
+                ui_command_sender.send(UiCommand::Detach).ok();
             }
             #[cfg(windows)]
             "neovide.register_right_click" => {
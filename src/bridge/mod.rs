@@ -1,30 +1,49 @@
+mod clipboard;
 pub mod create;
+mod diagnostics;
 mod events;
 mod handler;
+mod recording;
 mod tx_wrapper;
 mod ui_commands;
 
 use std::env;
+use std::io;
 use std::path::Path;
 use std::process::Stdio;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
-use crossfire::mpsc::{RxUnbounded, TxUnbounded};
+use crossfire::mpsc::{unbounded_future, RxUnbounded, TxUnbounded};
 use log::{error, info, warn};
-use nvim_rs::UiAttachOptions;
+use nvim_rs::{
+    error::{CallError, LoopError},
+    Neovim, UiAttachOptions,
+};
+use parking_lot::{Mutex, RwLock};
 use rmpv::Value;
 use tokio::process::Command;
 use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
+use tokio::time::{delay_for, timeout};
 
 use crate::error_handling::ResultPanicExplanation;
 use crate::settings::*;
 use crate::window::window_geometry_or_default;
+use clipboard::CLIPBOARD_PROVIDER_SETUP;
 pub use events::*;
 use handler::NeovimHandler;
+pub use recording::recorded_grid_size;
+use recording::{replay_path, replay_recording};
 pub use tx_wrapper::{TxWrapper, WrapTx};
+use ui_commands::coalesce_ui_commands;
 pub use ui_commands::UiCommand;
 
+const RECONNECT_INITIAL_DELAY: Duration = Duration::from_millis(250);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(10);
+const DETACH_TIMEOUT: Duration = Duration::from_secs(1);
+
 #[cfg(windows)]
 fn set_windows_creation_flags(cmd: &mut Command) {
     cmd.creation_flags(0x0800_0000); // CREATE_NO_WINDOW
@@ -94,7 +113,6 @@ fn build_nvim_cmd() -> Command {
     }
 }
 
-#[cfg(windows)]
 pub fn build_neovide_command(channel: u64, num_args: u64, command: &str, event: &str) -> String {
     let nargs: String = if num_args > 1 {
         "+".to_string()
@@ -135,66 +153,74 @@ pub fn create_nvim_command() -> Command {
 enum ConnectionMode {
     Child,
     RemoteTcp(String),
+    UnixSocket(String),
+}
+
+impl ConnectionMode {
+    // Remote sessions are owned by someone else and should outlive the gui.
+    fn is_remote(&self) -> bool {
+        !matches!(self, ConnectionMode::Child)
+    }
 }
 
 fn connection_mode() -> ConnectionMode {
+    parse_connection_mode(&env::args().collect::<Vec<_>>())
+}
+
+fn parse_connection_mode(args: &[String]) -> ConnectionMode {
     let tcp_prefix = "--remote-tcp=";
+    let server_prefix = "--server=";
 
-    if let Some(arg) = std::env::args().find(|arg| arg.starts_with(tcp_prefix)) {
+    if let Some(arg) = args.iter().find(|arg| arg.starts_with(tcp_prefix)) {
         let input = &arg[tcp_prefix.len()..];
         ConnectionMode::RemoteTcp(input.to_owned())
+    } else if let Some(arg) = args.iter().find(|arg| arg.starts_with(server_prefix)) {
+        let input = &arg[server_prefix.len()..];
+        ConnectionMode::UnixSocket(input.to_owned())
     } else {
         ConnectionMode::Child
     }
 }
 
-async fn start_neovim_runtime(
-    ui_command_sender: TxUnbounded<UiCommand>,
-    ui_command_receiver: RxUnbounded<UiCommand>,
-    redraw_event_sender: TxUnbounded<RedrawEvent>,
-    running: Arc<AtomicBool>,
-) {
-    let (width, height) = window_geometry_or_default();
-    let handler = NeovimHandler::new(ui_command_sender.clone(), redraw_event_sender.clone());
-    let (mut nvim, io_handler) = match connection_mode() {
+// Tokio has no named pipe support yet, so `--server` only works where unix domain sockets do.
+// Everywhere else it fails with this error rather than silently falling back to a child process.
+#[cfg(not(unix))]
+const UNIX_SOCKET_UNSUPPORTED: &str =
+    "--server is only supported on unix (Linux and macOS), use --remote-tcp instead";
+
+async fn connect(
+    connection_mode: &ConnectionMode,
+    handler: NeovimHandler,
+) -> io::Result<(Neovim<TxWrapper>, JoinHandle<Result<(), Box<LoopError>>>)> {
+    match connection_mode {
         ConnectionMode::Child => create::new_child_cmd(&mut create_nvim_command(), handler).await,
-        ConnectionMode::RemoteTcp(address) => create::new_tcp(address, handler).await,
+        ConnectionMode::RemoteTcp(address) => create::new_tcp(address.as_str(), handler).await,
+        #[cfg(unix)]
+        ConnectionMode::UnixSocket(path) => create::new_unix(path, handler).await,
+        #[cfg(not(unix))]
+        ConnectionMode::UnixSocket(_) => Err(io::Error::new(
+            io::ErrorKind::Other,
+            UNIX_SOCKET_UNSUPPORTED,
+        )),
     }
-    .unwrap_or_explained_panic("Could not locate or start neovim process");
+}
 
+// Prepares a freshly connected neovim for the gui. This is repeated for every new connection
+// since a restarted server has lost all of it. Errors are left to the caller, since a failed
+// reconnect attempt is retried rather than fatal.
+async fn setup_neovim(nvim: &Neovim<TxWrapper>) -> Result<(), String> {
     if nvim.get_api_info().await.is_err() {
-        error!("Cannot get neovim api info, either neovide is launched with an unknown command line option or neovim version not supported!");
-        std::process::exit(-1);
+        return Err(String::from("Cannot get neovim api info, either neovide is launched with an unknown command line option or neovim version not supported!"));
     }
 
-    let close_watcher_running = running.clone();
-    tokio::spawn(async move {
-        info!("Close watcher started");
-        match io_handler.await {
-            Err(join_error) => error!("Error joining IO loop: '{}'", join_error),
-            Ok(Err(error)) => {
-                if !error.is_channel_closed() {
-                    error!("Error: '{}'", error);
-                }
-            }
-            Ok(Ok(())) => {}
-        };
-        close_watcher_running.store(false, Ordering::Relaxed);
-    });
-
-    if let Ok(Value::Integer(correct_version)) = nvim.eval("has(\"nvim-0.4\")").await {
-        if correct_version.as_i64() != Some(1) {
-            error!("Neovide requires version 0.4 or higher");
-            std::process::exit(0);
-        }
-    } else {
-        error!("Neovide requires version 0.4 or higher");
-        std::process::exit(0);
-    };
+    match nvim.eval("has(\"nvim-0.4\")").await {
+        Ok(Value::Integer(correct_version)) if correct_version.as_i64() == Some(1) => {}
+        _ => return Err(String::from("Neovide requires version 0.4 or higher")),
+    }
 
     nvim.set_var("neovide", Value::Boolean(true))
         .await
-        .unwrap_or_explained_panic("Could not communicate with neovim process");
+        .map_err(|error| format!("Could not communicate with neovim process: {}", error))?;
 
     if let Err(command_error) = nvim.command("runtime! ginit.vim").await {
         nvim.command(&format!(
@@ -240,6 +266,29 @@ async fn start_neovim_runtime(
         neovide_channel
     );
 
+    nvim.set_var("neovide_channel_id", Value::from(neovide_channel))
+        .await
+        .ok();
+    nvim.command(CLIPBOARD_PROVIDER_SETUP).await.ok();
+
+    nvim.command(&build_neovide_command(
+        neovide_channel,
+        0,
+        "NeovideDetach",
+        "detach",
+    ))
+    .await
+    .ok();
+
+    nvim.command(&build_neovide_command(
+        neovide_channel,
+        0,
+        "NeovideDiagnostics",
+        "diagnostics",
+    ))
+    .await
+    .ok();
+
     #[cfg(windows)]
     nvim.command(&build_neovide_command(
         neovide_channel,
@@ -267,39 +316,230 @@ async fn start_neovim_runtime(
         .await
         .ok();
 
-    let mut options = UiAttachOptions::new();
-    options.set_linegrid_external(true);
-    if env::args().any(|arg| arg == "--multiGrid") || env::var("NeovideMultiGrid").is_ok() {
-        options.set_multigrid_external(true);
+    Ok(())
+}
+
+// Logs why the connection's io loop ended.
+async fn wait_for_disconnect(io_handler: JoinHandle<Result<(), Box<LoopError>>>) {
+    match io_handler.await {
+        Err(join_error) => error!("Error joining IO loop: '{}'", join_error),
+        Ok(Err(error)) => {
+            if !error.is_channel_closed() {
+                error!("Error: '{}'", error);
+            }
+        }
+        Ok(Ok(())) => {}
+    };
+}
+
+// Tries to connect again with exponential backoff until it succeeds or the gui is closed. The
+// renderer shows a reconnecting overlay in the meantime.
+async fn reconnect(
+    connection_mode: &ConnectionMode,
+    handler: &NeovimHandler,
+    grid_size: (u64, u64),
+    redraw_event_sender: &TxUnbounded<RedrawEvent>,
+    running: &AtomicBool,
+) -> Option<(Neovim<TxWrapper>, JoinHandle<Result<(), Box<LoopError>>>)> {
+    redraw_event_sender.send(RedrawEvent::ConnectionLost).ok();
+
+    let mut delay = RECONNECT_INITIAL_DELAY;
+    while running.load(Ordering::Relaxed) {
+        info!("Reconnecting to neovim in {:?}", delay);
+        delay_for(delay).await;
+        delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+
+        let (nvim, io_handler) = match connect(connection_mode, handler.clone()).await {
+            Ok(connection) => connection,
+            Err(error) => {
+                warn!("Could not reconnect to neovim: {}", error);
+                continue;
+            }
+        };
+
+        if let Err(error) = setup_neovim(&nvim).await {
+            warn!("Could not set up neovim after reconnecting: {}", error);
+            continue;
+        }
+
+        let (width, height) = grid_size;
+        redraw_event_sender.send(RedrawEvent::UiReattach).ok();
+        if let Err(error) = attach_ui(&nvim, width, height).await {
+            warn!("Could not attach ui after reconnecting: {}", error);
+            continue;
+        }
+
+        // A restarted server has none of the setting watchers anymore
+        SETTINGS.read_initial_values(&nvim).await;
+        SETTINGS.setup_changed_listeners(&nvim).await;
+
+        info!("Reconnected to neovim");
+        return Some((nvim, io_handler));
     }
-    options.set_rgb(true);
-    nvim.ui_attach(width as i64, height as i64, &options)
+
+    None
+}
+
+async fn start_neovim_runtime(
+    ui_command_sender: TxUnbounded<UiCommand>,
+    ui_command_receiver: RxUnbounded<UiCommand>,
+    redraw_event_sender: TxUnbounded<RedrawEvent>,
+    running: Arc<AtomicBool>,
+) {
+    let (width, height) = window_geometry_or_default();
+    let handler = NeovimHandler::new(ui_command_sender.clone(), redraw_event_sender.clone());
+    let connection_mode = connection_mode();
+    let remote = connection_mode.is_remote();
+    let (nvim, io_handler) = connect(&connection_mode, handler.clone())
+        .await
+        .unwrap_or_explained_panic("Could not locate or start neovim process");
+
+    if let Err(error) = setup_neovim(&nvim).await {
+        error!("{}", error);
+        std::process::exit(-1);
+    }
+
+    attach_ui(&nvim, width, height)
         .await
         .unwrap_or_explained_panic("Could not attach ui to neovim process");
 
     info!("Neovim process attached");
 
     let nvim = Arc::new(nvim);
+    // Swapped out whenever a dropped remote connection is reestablished
+    let current_nvim = Arc::new(RwLock::new(nvim.clone()));
+    let connected = Arc::new(AtomicBool::new(true));
+    let attached = Arc::new(AtomicBool::new(true));
+    let grid_size = Arc::new(Mutex::new((width, height)));
+
+    let close_watcher_running = running.clone();
+    let close_watcher_nvim = current_nvim.clone();
+    let close_watcher_connected = connected.clone();
+    let close_watcher_attached = attached.clone();
+    let close_watcher_grid_size = grid_size.clone();
+    let close_watcher_redraw_event_sender = redraw_event_sender.clone();
+    tokio::spawn(async move {
+        info!("Close watcher started");
+        let mut io_handler = io_handler;
+        loop {
+            wait_for_disconnect(io_handler).await;
+
+            // Only remote sessions are worth reconnecting to, a child process exiting means the
+            // user quit neovim.
+            if !remote || !close_watcher_running.load(Ordering::Relaxed) {
+                break;
+            }
+
+            close_watcher_connected.store(false, Ordering::Relaxed);
+            let grid_size = *close_watcher_grid_size.lock();
+            match reconnect(
+                &connection_mode,
+                &handler,
+                grid_size,
+                &close_watcher_redraw_event_sender,
+                &close_watcher_running,
+            )
+            .await
+            {
+                Some((new_nvim, new_io_handler)) => {
+                    *close_watcher_nvim.write() = Arc::new(new_nvim);
+                    close_watcher_attached.store(true, Ordering::Relaxed);
+                    close_watcher_connected.store(true, Ordering::Relaxed);
+                    io_handler = new_io_handler;
+                }
+                None => break,
+            }
+        }
+        close_watcher_running.store(false, Ordering::Relaxed);
+    });
+
+    // Quitting is handled as soon as it arrives instead of behind input which may be stuck on a
+    // hung server or a half open connection, so the window can always be closed.
+    let (dispatch_sender, dispatch_receiver) = unbounded_future();
+    let quit_running = running.clone();
+    let quit_nvim = current_nvim.clone();
+    let quit_connected = connected.clone();
+    let quit_attached = attached.clone();
+    tokio::spawn(async move {
+        while let Ok(ui_command) = ui_command_receiver.recv().await {
+            if let UiCommand::Quit = ui_command {
+                if remote
+                    && quit_attached.load(Ordering::Relaxed)
+                    && quit_connected.load(Ordering::Relaxed)
+                {
+                    info!("Detaching from remote neovim session");
+                    let nvim = quit_nvim.read().clone();
+                    timeout(DETACH_TIMEOUT, nvim.ui_detach()).await.ok();
+                }
+                break;
+            }
 
+            if dispatch_sender.send(ui_command).is_err() {
+                break;
+            }
+        }
+        quit_running.store(false, Ordering::Relaxed);
+    });
+
+    // Commands are sent one at a time and in order. Whatever piled up while the previous ones were
+    // in flight is coalesced into as few calls as possible.
     let ui_command_running = running.clone();
-    let input_nvim = nvim.clone();
     tokio::spawn(async move {
         loop {
             if !ui_command_running.load(Ordering::Relaxed) {
                 break;
             }
 
-            match ui_command_receiver.recv().await {
-                Ok(ui_command) => {
-                    let input_nvim = input_nvim.clone();
-                    tokio::spawn(async move {
-                        ui_command.execute(&input_nvim).await;
-                    });
-                }
+            let mut ui_commands = match dispatch_receiver.recv().await {
+                Ok(ui_command) => vec![ui_command],
                 Err(_) => {
                     ui_command_running.store(false, Ordering::Relaxed);
                     break;
                 }
+            };
+            while let Ok(ui_command) = dispatch_receiver.try_recv() {
+                ui_commands.push(ui_command);
+            }
+
+            for ui_command in coalesce_ui_commands(ui_commands) {
+                if let UiCommand::Resize { width, height } = ui_command {
+                    *grid_size.lock() = (width as u64, height as u64);
+                }
+
+                let input_nvim = current_nvim.read().clone();
+                let connected = connected.load(Ordering::Relaxed);
+                let is_attached = attached.load(Ordering::Relaxed);
+
+                match ui_command {
+                    // Input can't reach neovim while reconnecting
+                    _ if !connected => {}
+                    UiCommand::Detach => {
+                        if is_attached {
+                            info!("Ui detached, press any key to reattach");
+                            input_nvim.ui_detach().await.ok();
+                            attached.store(false, Ordering::Relaxed);
+                        }
+                    }
+                    ui_command @ UiCommand::Keyboard(_) if !is_attached => {
+                        let (width, height) = *grid_size.lock();
+                        redraw_event_sender.send(RedrawEvent::UiReattach).ok();
+                        match attach_ui(&input_nvim, width, height).await {
+                            Ok(()) => {
+                                info!("Ui reattached");
+                                attached.store(true, Ordering::Relaxed);
+                                // The key that woke the ui up is typed into the reattached session
+                                ui_command.execute(&input_nvim).await;
+                            }
+                            Err(error) => error!("Could not reattach ui: {}", error),
+                        }
+                    }
+                    _ if !is_attached => {}
+                    // Keys answering a prompt the command stops at must not wait for it to finish
+                    ui_command if ui_command.runs_ex_command() => {
+                        tokio::spawn(async move { ui_command.execute(&input_nvim).await });
+                    }
+                    ui_command => ui_command.execute(&input_nvim).await,
+                }
             }
         }
     });
@@ -308,6 +548,63 @@ async fn start_neovim_runtime(
     SETTINGS.setup_changed_listeners(&nvim).await;
 }
 
+// The optional ui extensions turned on with a command line argument or environment variable,
+// named the way ui_attach names them. Recordings store these to describe the session.
+const UI_EXTENSIONS: [(&str, &str, &str); 5] = [
+    ("--multiGrid", "NeovideMultiGrid", "ext_multigrid"),
+    ("--extCmdline", "NeovideExtCmdline", "ext_cmdline"),
+    ("--extMessages", "NeovideExtMessages", "ext_messages"),
+    ("--extPopupmenu", "NeovideExtPopupmenu", "ext_popupmenu"),
+    ("--extTabline", "NeovideExtTabline", "ext_tabline"),
+];
+
+fn enabled_ui_extensions() -> Vec<&'static str> {
+    UI_EXTENSIONS
+        .iter()
+        .filter(|(argument, variable, _)| {
+            env::args().any(|arg| arg == *argument) || env::var(variable).is_ok()
+        })
+        .map(|(_, _, extension)| *extension)
+        .collect()
+}
+
+// Turns on one of the extensions named by enabled_ui_extensions. Unknown names are reported
+// rather than guessed at.
+fn enable_ui_extension(options: &mut UiAttachOptions, extension: &str) -> bool {
+    match extension {
+        "ext_multigrid" => options.set_multigrid_external(true),
+        "ext_cmdline" => options.set_cmdline_external(true),
+        // Neovim implies ext_cmdline whenever ext_messages is enabled
+        "ext_messages" => options.set_messages_external(true),
+        "ext_popupmenu" => options.set_popupmenu_external(true),
+        "ext_tabline" => options.set_tabline_external(true),
+        _ => {
+            warn!("Ignoring unknown ui extension {}", extension);
+            return false;
+        }
+    };
+    true
+}
+
+fn ui_attach_options() -> UiAttachOptions {
+    let mut options = UiAttachOptions::new();
+    options.set_linegrid_external(true);
+    for extension in enabled_ui_extensions() {
+        enable_ui_extension(&mut options, extension);
+    }
+    options.set_rgb(true);
+    options
+}
+
+async fn attach_ui(
+    nvim: &Neovim<TxWrapper>,
+    width: u64,
+    height: u64,
+) -> Result<(), Box<CallError>> {
+    nvim.ui_attach(width as i64, height as i64, &ui_attach_options())
+        .await
+}
+
 pub struct Bridge {
     _runtime: Runtime, // Necessary to keep runtime running
 }
@@ -319,11 +616,74 @@ pub fn start_bridge(
     running: Arc<AtomicBool>,
 ) -> Bridge {
     let runtime = Runtime::new().unwrap();
-    runtime.spawn(start_neovim_runtime(
-        ui_command_sender,
-        ui_command_receiver,
-        redraw_event_sender,
-        running,
-    ));
+    if let Some(path) = replay_path() {
+        runtime.spawn(replay_recording(
+            path,
+            ui_command_receiver,
+            redraw_event_sender,
+            running,
+        ));
+    } else {
+        runtime.spawn(start_neovim_runtime(
+            ui_command_sender,
+            ui_command_receiver,
+            redraw_event_sender,
+            running,
+        ));
+    }
     Bridge { _runtime: runtime }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_ui_extension_is_known() {
+        let mut options = UiAttachOptions::new();
+        for (_, _, extension) in UI_EXTENSIONS.iter() {
+            assert!(enable_ui_extension(&mut options, extension));
+        }
+    }
+
+    #[test]
+    fn test_unknown_ui_extension_is_ignored() {
+        let mut options = UiAttachOptions::new();
+        assert!(!enable_ui_extension(&mut options, "ext_tabine"));
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_server_argument_selects_unix_socket() {
+        let mode = parse_connection_mode(&args(&["neovide", "--server=/tmp/nvim.sock"]));
+        assert!(matches!(mode, ConnectionMode::UnixSocket(path) if path == "/tmp/nvim.sock"));
+    }
+
+    #[test]
+    fn test_remote_tcp_takes_precedence_over_server() {
+        let mode = parse_connection_mode(&args(&[
+            "neovide",
+            "--server=/tmp/nvim.sock",
+            "--remote-tcp=localhost:6666",
+        ]));
+        assert!(matches!(mode, ConnectionMode::RemoteTcp(address) if address == "localhost:6666"));
+    }
+
+    #[cfg(not(unix))]
+    #[test]
+    fn test_server_is_unix_only() {
+        let (ui_command_sender, _ui_command_receiver) = unbounded_future();
+        let (redraw_event_sender, _redraw_event_receiver) = unbounded_future();
+        let handler = NeovimHandler::new(ui_command_sender, redraw_event_sender);
+        let mode = ConnectionMode::UnixSocket("nvim.sock".to_owned());
+
+        let result = Runtime::new().unwrap().block_on(connect(&mode, handler));
+        let error = result
+            .err()
+            .expect("--server should not connect on this platform");
+        assert_eq!(error.to_string(), UNIX_SOCKET_UNSUPPORTED);
+    }
+}
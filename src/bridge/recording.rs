@@ -0,0 +1,323 @@
+//! Recording and replaying of the raw redraw notifications sent by neovim. A recording starts with
+//! a msgpack map describing the session, holding the grid size and ui extensions the gui attached
+//! with, followed by a sequence of msgpack arrays, each holding the microseconds since recording
+//! started followed by the arguments of one redraw notification.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossfire::mpsc::{RxUnbounded, TxUnbounded};
+use log::{error, info};
+use parking_lot::Mutex;
+use rmpv::Value;
+use tokio::time::delay_for;
+
+use super::diagnostics::DIAGNOSTICS;
+use super::events::{parse_redraw_event, RedrawEvent};
+use super::ui_commands::UiCommand;
+use crate::error_handling::ResultPanicExplanation;
+use crate::window::window_geometry_or_default;
+
+pub fn record_path() -> Option<String> {
+    let prefix = "--record=";
+    std::env::args()
+        .find(|arg| arg.starts_with(prefix))
+        .map(|arg| arg[prefix.len()..].to_owned())
+}
+
+pub fn replay_path() -> Option<String> {
+    let prefix = "--replay=";
+    std::env::args()
+        .find(|arg| arg.starts_with(prefix))
+        .map(|arg| arg[prefix.len()..].to_owned())
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RecordingHeader {
+    pub grid_size: (u64, u64),
+    pub ui_extensions: Vec<String>,
+}
+
+impl RecordingHeader {
+    fn to_value(&self) -> Value {
+        let (width, height) = self.grid_size;
+        Value::Map(vec![
+            (
+                Value::from("grid_size"),
+                Value::Array(vec![Value::from(width), Value::from(height)]),
+            ),
+            (
+                Value::from("ui_extensions"),
+                Value::Array(
+                    self.ui_extensions
+                        .iter()
+                        .map(|extension| Value::from(extension.as_str()))
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    fn from_value(value: Value) -> Result<RecordingHeader, String> {
+        let mut grid_size = None;
+        let mut ui_extensions = Vec::new();
+
+        for (key, value) in value.as_map().ok_or("The header is not a map")? {
+            match key.as_str() {
+                Some("grid_size") => {
+                    grid_size = match value.as_array().map(|size| &size[..]) {
+                        Some([width, height]) => width.as_u64().zip(height.as_u64()),
+                        _ => None,
+                    };
+                }
+                Some("ui_extensions") => {
+                    ui_extensions = value
+                        .as_array()
+                        .ok_or("The header's ui extensions are not an array")?
+                        .iter()
+                        .filter_map(|extension| extension.as_str().map(String::from))
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+
+        Ok(RecordingHeader {
+            grid_size: grid_size.ok_or("The header has no valid grid size")?,
+            ui_extensions,
+        })
+    }
+}
+
+fn write_record(
+    writer: &mut impl Write,
+    timestamp: Duration,
+    arguments: Vec<Value>,
+) -> io::Result<()> {
+    let record = Value::Array(vec![
+        Value::from(timestamp.as_micros() as u64),
+        Value::Array(arguments),
+    ]);
+    rmpv::encode::write_value(writer, &record).map_err(io::Error::from)
+}
+
+type Records = Vec<(Duration, Vec<Value>)>;
+
+// Recordings made before the header was added start with the first record instead.
+fn read_recording(data: &[u8]) -> Result<(Option<RecordingHeader>, Records), String> {
+    let mut remaining = data;
+    let mut header = None;
+    let mut records = Vec::new();
+
+    while !remaining.is_empty() {
+        let record = rmpv::decode::read_value(&mut remaining)
+            .map_err(|error| format!("Could not decode record {}: {}", records.len(), error))?;
+
+        match record {
+            Value::Map(_) if header.is_none() && records.is_empty() => {
+                header = Some(RecordingHeader::from_value(record)?);
+            }
+            Value::Array(mut parts) if parts.len() == 2 => {
+                let arguments = match parts.pop() {
+                    Some(Value::Array(arguments)) => arguments,
+                    _ => return Err(format!("Record {} has no arguments", records.len())),
+                };
+                let timestamp = parts[0]
+                    .as_u64()
+                    .ok_or_else(|| format!("Record {} has no timestamp", records.len()))?;
+                records.push((Duration::from_micros(timestamp), arguments));
+            }
+            _ => {
+                return Err(format!(
+                    "Record {} is not a [timestamp, arguments] pair",
+                    records.len()
+                ))
+            }
+        }
+    }
+
+    Ok((header, records))
+}
+
+// The grid size to open the window with when replaying, so that the recorded layout fits
+pub fn recorded_grid_size() -> Option<(u64, u64)> {
+    let file = File::open(replay_path()?).ok()?;
+    match rmpv::decode::read_value(&mut BufReader::new(file)).ok()? {
+        header @ Value::Map(_) => RecordingHeader::from_value(header)
+            .ok()
+            .map(|header| header.grid_size),
+        _ => None,
+    }
+}
+
+pub struct EventRecorder {
+    sender: Mutex<Sender<(Duration, Vec<Value>)>>,
+    start: Instant,
+}
+
+impl EventRecorder {
+    // Records are written by a thread of their own so that notifications are never held up by the
+    // disk. Each record is flushed immediately so that a recording survives the crash it is meant
+    // to capture.
+    pub fn new(path: &str) -> io::Result<EventRecorder> {
+        info!("Recording redraw events to {}", path);
+        let mut writer = BufWriter::new(File::create(path)?);
+        let header = RecordingHeader {
+            grid_size: window_geometry_or_default(),
+            ui_extensions: super::enabled_ui_extensions()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        };
+        rmpv::encode::write_value(&mut writer, &header.to_value()).map_err(io::Error::from)?;
+        writer.flush()?;
+
+        let (sender, receiver) = channel::<(Duration, Vec<Value>)>();
+        thread::Builder::new()
+            .name(String::from("recorder"))
+            .spawn(move || {
+                for (timestamp, arguments) in receiver {
+                    if let Err(error) =
+                        write_record(&mut writer, timestamp, arguments).and_then(|_| writer.flush())
+                    {
+                        error!("Could not record redraw event: {}", error);
+                    }
+                }
+            })?;
+
+        Ok(EventRecorder {
+            sender: Mutex::new(sender),
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(&self, arguments: &[Value]) {
+        self.sender
+            .lock()
+            .send((self.start.elapsed(), arguments.to_vec()))
+            .ok();
+    }
+}
+
+// Plays a recording back into the editor in place of a neovim process. Ui commands have nowhere
+// to go, so everything but quitting is dropped.
+pub async fn replay_recording(
+    path: String,
+    ui_command_receiver: RxUnbounded<UiCommand>,
+    redraw_event_sender: TxUnbounded<RedrawEvent>,
+    running: Arc<AtomicBool>,
+) {
+    let real_time = !std::env::args().any(|arg| arg == "--replay-fast");
+    let data = std::fs::read(&path).unwrap_or_explained_panic("Could not read replay file");
+    let (header, records) =
+        read_recording(&data).unwrap_or_explained_panic("Could not parse replay file");
+    info!(
+        "Replaying {} redraw notifications from {}",
+        records.len(),
+        path
+    );
+    if let Some(header) = header {
+        let (width, height) = header.grid_size;
+        info!(
+            "Recorded at {}x{} with ui extensions: {}",
+            width,
+            height,
+            header.ui_extensions.join(", ")
+        );
+    }
+
+    let ui_command_running = running.clone();
+    tokio::spawn(async move {
+        while let Ok(ui_command) = ui_command_receiver.recv().await {
+            if let UiCommand::Quit = ui_command {
+                break;
+            }
+        }
+        ui_command_running.store(false, Ordering::Relaxed);
+    });
+
+    let start = Instant::now();
+    for (timestamp, arguments) in records {
+        if !running.load(Ordering::Relaxed) {
+            return;
+        }
+
+        if real_time {
+            let elapsed = start.elapsed();
+            if timestamp > elapsed {
+                delay_for(timestamp - elapsed).await;
+            }
+        }
+
+        for events in arguments {
+            match parse_redraw_event(events) {
+                Ok(parsed_events) => {
+                    for parsed_event in parsed_events {
+                        redraw_event_sender.send(parsed_event).ok();
+                    }
+                }
+                Err(error) => DIAGNOSTICS.record_dropped_event(&error),
+            }
+        }
+    }
+
+    info!("Replay finished");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_round_trip() {
+        let first = vec![Value::from("flush")];
+        let second = vec![Value::from("mouse_on"), Value::from("busy_stop")];
+
+        let header = RecordingHeader {
+            grid_size: (80, 24),
+            ui_extensions: vec![String::from("ext_multigrid")],
+        };
+
+        let mut data = Vec::new();
+        rmpv::encode::write_value(&mut data, &header.to_value()).unwrap();
+        write_record(&mut data, Duration::from_micros(10), first.clone()).unwrap();
+        write_record(&mut data, Duration::from_millis(20), second.clone()).unwrap();
+
+        let (read_header, records) = read_recording(&data).unwrap();
+        assert_eq!(read_header, Some(header));
+        assert_eq!(
+            records,
+            vec![
+                (Duration::from_micros(10), first),
+                (Duration::from_millis(20), second)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recording_without_header() {
+        let mut data = Vec::new();
+        write_record(
+            &mut data,
+            Duration::from_micros(10),
+            vec![Value::from("flush")],
+        )
+        .unwrap();
+
+        let (header, records) = read_recording(&data).unwrap();
+        assert_eq!(header, None);
+        assert_eq!(records.len(), 1);
+    }
+
+    #[test]
+    fn test_malformed_recording() {
+        let mut data = Vec::new();
+        rmpv::encode::write_value(&mut data, &Value::from("flush")).unwrap();
+        assert!(read_recording(&data).is_err());
+    }
+}
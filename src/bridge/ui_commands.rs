@@ -1,11 +1,10 @@
-use log::trace;
+use log::{error, trace};
 
-#[cfg(windows)]
-use log::error;
-
-use nvim_rs::Neovim;
+use nvim_rs::{error::CallError, Neovim};
+use rmpv::Value;
 
 use crate::bridge::TxWrapper;
+use crate::window::FileDropMode;
 
 #[cfg(windows)]
 use crate::windows_utils::{
@@ -19,98 +18,399 @@ pub enum UiCommand {
         height: u32,
     },
     Keyboard(String),
+    Paste(String),
+    Copy,
+    SetScaleFactor(f32),
     MouseButton {
+        button: String,
         action: String,
         grid_id: u64,
         position: (u32, u32),
+        modifiers: String,
     },
     Scroll {
         direction: String,
         grid_id: u64,
         position: (u32, u32),
+        modifiers: String,
     },
     Drag {
+        button: String,
         grid_id: u64,
         position: (u32, u32),
+        modifiers: String,
+    },
+    FileDrop {
+        paths: Vec<String>,
+        window_handle: Option<u64>,
+        mode: FileDropMode,
     },
-    FileDrop(String),
+    TabSelect(u64),
+    TabClose(u64),
+    TabMove {
+        from: u64,
+        to: u64,
+    },
+    ResizeGrid {
+        grid_id: u64,
+        width: u32,
+        height: u32,
+    },
+    CloseWindow(u64),
     FocusLost,
     FocusGained,
+    Detach,
+    Quit,
     #[cfg(windows)]
     RegisterRightClick,
     #[cfg(windows)]
     UnregisterRightClick,
 }
 
+// Large pastes are streamed to neovim in pieces so that a single huge request doesn't stall the
+// connection.
+const PASTE_CHUNK_SIZE: usize = 4096;
+
+// Splits text into the chunks and phases nvim_paste expects: -1 for a paste sent in one call,
+// otherwise 1 for the first chunk, 2 for the ones in between and 3 for the last.
+fn paste_chunks(text: &str) -> Vec<(&str, i64)> {
+    let mut chunks = Vec::new();
+    let mut remaining = text;
+    while remaining.len() > PASTE_CHUNK_SIZE {
+        let mut split = PASTE_CHUNK_SIZE;
+        while !remaining.is_char_boundary(split) {
+            split -= 1;
+        }
+        let (chunk, rest) = remaining.split_at(split);
+        chunks.push(chunk);
+        remaining = rest;
+    }
+    chunks.push(remaining);
+
+    let last = chunks.len() - 1;
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let phase = match index {
+                _ if last == 0 => -1,
+                0 => 1,
+                _ if index == last => 3,
+                _ => 2,
+            };
+            (chunk, phase)
+        })
+        .collect()
+}
+
+// :drop opens every file at once as the new argument list, while the other modes open each file
+// in its own split or tab.
+fn file_drop_commands(mode: FileDropMode, escaped_paths: &[String]) -> Vec<String> {
+    match mode {
+        FileDropMode::Drop => vec![format!("drop {}", escaped_paths.join(" "))],
+        _ => escaped_paths
+            .iter()
+            .map(|path| format!("{} {}", mode.command(), path))
+            .collect(),
+    }
+}
+
+// Charwise, linewise and blockwise visual mode. Select mode is left out since typing there
+// replaces the selection.
+fn is_visual_mode(mode: &str) -> bool {
+    matches!(mode, "v" | "V" | "\u{16}")
+}
+
+// nvim_input_mouse only knows the left, right and middle buttons, so the side buttons are typed as
+// their key codes instead, such as <S-X1Mouse>.
+fn side_button_keys(button: &str, action: &str, modifiers: &str) -> Option<String> {
+    let button = match button {
+        "x1" => "X1",
+        "x2" => "X2",
+        _ => return None,
+    };
+    let action = match action {
+        "press" => "Mouse",
+        "release" => "Release",
+        _ => "Drag",
+    };
+    Some(format!("<{}{}{}>", modifiers, button, action))
+}
+
+// The side button keys act at the last mouse position neovim knows about, so the mouse is moved
+// to the cell first.
+async fn input_side_button(
+    nvim: &Neovim<TxWrapper>,
+    keys: &str,
+    grid_id: u64,
+    (grid_x, grid_y): (u32, u32),
+) {
+    log_failure(
+        nvim.input_mouse("move", "", "", grid_id as i64, grid_y as i64, grid_x as i64)
+            .await,
+        "Mouse Move Failed",
+    );
+    log_failure(nvim.input(keys).await, "Mouse Input Failed");
+}
+
+fn log_failure<T>(result: Result<T, Box<CallError>>, description: &str) {
+    if let Err(error) = result {
+        error!("{}: {}", description, error);
+    }
+}
+
+// Merges runs of commands which can be sent to neovim as one. Keys typed faster than neovim
+// receives them are joined into a single input call, while only the latest position of a drag or
+// resize matters. Scrolls are kept apart since every wheel tick scrolls once more.
+pub fn coalesce_ui_commands(ui_commands: Vec<UiCommand>) -> Vec<UiCommand> {
+    let mut coalesced: Vec<UiCommand> = Vec::with_capacity(ui_commands.len());
+
+    for ui_command in ui_commands {
+        let merged = match (coalesced.last_mut(), &ui_command) {
+            (Some(UiCommand::Keyboard(keys)), UiCommand::Keyboard(new_keys)) => {
+                keys.push_str(new_keys);
+                true
+            }
+            (
+                Some(UiCommand::Drag {
+                    button,
+                    grid_id,
+                    position,
+                    modifiers,
+                }),
+                UiCommand::Drag {
+                    button: new_button,
+                    grid_id: new_grid_id,
+                    position: new_position,
+                    modifiers: new_modifiers,
+                },
+            ) if button == new_button && *grid_id == *new_grid_id && modifiers == new_modifiers => {
+                *position = *new_position;
+                true
+            }
+            (
+                Some(UiCommand::Resize { width, height }),
+                UiCommand::Resize {
+                    width: new_width,
+                    height: new_height,
+                },
+            ) => {
+                *width = *new_width;
+                *height = *new_height;
+                true
+            }
+            _ => false,
+        };
+
+        if !merged {
+            coalesced.push(ui_command);
+        }
+    }
+
+    coalesced
+}
+
 impl UiCommand {
+    /// Whether the command runs ex commands, which can stop at a prompt such as the swap file
+    /// warning until it is answered with keys that may be queued behind the command.
+    pub fn runs_ex_command(&self) -> bool {
+        matches!(
+            self,
+            UiCommand::FileDrop { .. }
+                | UiCommand::TabSelect(_)
+                | UiCommand::TabClose(_)
+                | UiCommand::TabMove { .. }
+                | UiCommand::CloseWindow(_)
+                | UiCommand::FocusLost
+                | UiCommand::FocusGained
+        )
+    }
+
     pub async fn execute(self, nvim: &Neovim<TxWrapper>) {
         match self {
-            UiCommand::Resize { width, height } => nvim
-                .ui_try_resize(width.max(10) as i64, height.max(3) as i64)
-                .await
-                .expect("Resize failed"),
+            UiCommand::Resize { width, height } => log_failure(
+                nvim.ui_try_resize(width.max(10) as i64, height.max(3) as i64)
+                    .await,
+                "Resize failed",
+            ),
             UiCommand::Keyboard(input_command) => {
                 trace!("Keyboard Input Sent: {}", input_command);
-                nvim.input(&input_command).await.expect("Input failed");
+                log_failure(nvim.input(&input_command).await, "Input failed");
             }
+            UiCommand::Paste(text) => {
+                trace!("Pasting {} bytes", text.len());
+                for (chunk, phase) in paste_chunks(&text) {
+                    match nvim.paste(chunk, true, phase).await {
+                        // Neovim asks us to stop when the paste was cancelled, for example with
+                        // <C-c>
+                        Ok(false) => break,
+                        Ok(true) => {}
+                        Err(error) => {
+                            error!("Paste failed: {}", error);
+                            break;
+                        }
+                    }
+                }
+            }
+            UiCommand::Copy => match nvim.get_mode().await {
+                // Only a visual selection has anything to copy. Yanking into the + register goes
+                // through the clipboard provider like any other yank.
+                Ok(mode)
+                    if mode.iter().any(|(key, value)| {
+                        key.as_str() == Some("mode") && value.as_str().map_or(false, is_visual_mode)
+                    }) =>
+                {
+                    log_failure(nvim.input("\"+y").await, "Copy failed")
+                }
+                Ok(_) => {}
+                Err(error) => error!("Could not read the mode to copy: {}", error),
+            },
+            UiCommand::SetScaleFactor(scale_factor) => log_failure(
+                nvim.set_var("neovide_scale_factor", Value::from(scale_factor as f64))
+                    .await,
+                "Could not set the scale factor",
+            ),
             UiCommand::MouseButton {
+                button,
                 action,
                 grid_id,
                 position: (grid_x, grid_y),
-            } => {
-                nvim.input_mouse(
-                    "left",
-                    &action,
-                    "",
-                    grid_id as i64,
-                    grid_y as i64,
-                    grid_x as i64,
-                )
-                .await
-                .expect("Mouse Input Failed");
-            }
+                modifiers,
+            } => match side_button_keys(&button, &action, &modifiers) {
+                Some(keys) => input_side_button(nvim, &keys, grid_id, (grid_x, grid_y)).await,
+                None => log_failure(
+                    nvim.input_mouse(
+                        &button,
+                        &action,
+                        &modifiers,
+                        grid_id as i64,
+                        grid_y as i64,
+                        grid_x as i64,
+                    )
+                    .await,
+                    "Mouse Input Failed",
+                ),
+            },
             UiCommand::Scroll {
                 direction,
                 grid_id,
                 position: (grid_x, grid_y),
-            } => {
+                modifiers,
+            } => log_failure(
                 nvim.input_mouse(
                     "wheel",
                     &direction,
-                    "",
+                    &modifiers,
                     grid_id as i64,
                     grid_y as i64,
                     grid_x as i64,
                 )
-                .await
-                .expect("Mouse Scroll Failed");
-            }
+                .await,
+                "Mouse Scroll Failed",
+            ),
             UiCommand::Drag {
+                button,
                 grid_id,
                 position: (grid_x, grid_y),
-            } => {
-                nvim.input_mouse(
-                    "left",
-                    "drag",
-                    "",
-                    grid_id as i64,
-                    grid_y as i64,
-                    grid_x as i64,
+                modifiers,
+            } => match side_button_keys(&button, "drag", &modifiers) {
+                Some(keys) => input_side_button(nvim, &keys, grid_id, (grid_x, grid_y)).await,
+                None => log_failure(
+                    nvim.input_mouse(
+                        &button,
+                        "drag",
+                        &modifiers,
+                        grid_id as i64,
+                        grid_y as i64,
+                        grid_x as i64,
+                    )
+                    .await,
+                    "Mouse Drag Failed",
+                ),
+            },
+            UiCommand::FocusLost => log_failure(
+                nvim.command("if exists('#FocusLost') | doautocmd <nomodeline> FocusLost | endif")
+                    .await,
+                "Focus Lost Failed",
+            ),
+            UiCommand::FocusGained => log_failure(
+                nvim.command(
+                    "if exists('#FocusGained') | doautocmd <nomodeline> FocusGained | endif",
                 )
-                .await
-                .expect("Mouse Drag Failed");
+                .await,
+                "Focus Gained Failed",
+            ),
+            UiCommand::FileDrop {
+                paths,
+                window_handle,
+                mode,
+            } => {
+                if let Some(window_handle) = window_handle {
+                    log_failure(
+                        nvim.command(&format!("call nvim_set_current_win({})", window_handle))
+                            .await,
+                        "Could not focus the drop target",
+                    );
+                }
+
+                let mut escaped_paths = Vec::new();
+                for path in paths {
+                    match nvim
+                        .call_function("fnameescape", vec![Value::from(path.as_str())])
+                        .await
+                    {
+                        Ok(Value::String(escaped)) => match escaped.into_str() {
+                            Some(escaped) => escaped_paths.push(escaped),
+                            None => error!("Could not escape dropped file {}", path),
+                        },
+                        Ok(_) => error!("Could not escape dropped file {}", path),
+                        Err(error) => error!("Could not escape dropped file {}: {}", path, error),
+                    }
+                }
+
+                if !escaped_paths.is_empty() {
+                    for command in file_drop_commands(mode, &escaped_paths) {
+                        log_failure(nvim.command(&command).await, "File drop failed");
+                    }
+                }
             }
-            UiCommand::FocusLost => nvim
-                .command("if exists('#FocusLost') | doautocmd <nomodeline> FocusLost | endif")
-                .await
-                .expect("Focus Lost Failed"),
-            UiCommand::FocusGained => nvim
-                .command("if exists('#FocusGained') | doautocmd <nomodeline> FocusGained | endif")
-                .await
-                .expect("Focus Gained Failed"),
-            UiCommand::FileDrop(path) => {
-                nvim.command(format!("e {}", path).as_str()).await.ok();
+            UiCommand::TabSelect(tab_number) => log_failure(
+                nvim.command(format!("tabnext {}", tab_number).as_str())
+                    .await,
+                "Tab Select Failed",
+            ),
+            UiCommand::TabClose(tab_number) => log_failure(
+                nvim.command(format!("tabclose {}", tab_number).as_str())
+                    .await,
+                "Tab Close Failed",
+            ),
+            UiCommand::TabMove { from, to } => {
+                // tabmove places the current tab after the given tab number, so moving left
+                // needs to target the tab before the destination
+                let destination = if to < from { to - 1 } else { to };
+                log_failure(
+                    nvim.command(format!("tabnext {} | tabmove {}", from, destination).as_str())
+                        .await,
+                    "Tab Move Failed",
+                );
             }
+            UiCommand::ResizeGrid {
+                grid_id,
+                width,
+                height,
+            } => log_failure(
+                nvim.ui_try_resize_grid(grid_id as i64, width.max(1) as i64, height.max(1) as i64)
+                    .await,
+                "Grid Resize Failed",
+            ),
+            UiCommand::CloseWindow(window_handle) => log_failure(
+                nvim.command(format!("call nvim_win_close({}, v:false)", window_handle).as_str())
+                    .await,
+                "Window Close Failed",
+            ),
+            // Attaching and shutting down change the state of the connection itself, so they
+            // are handled by the bridge's command loop instead.
+            UiCommand::Detach | UiCommand::Quit => {}
             #[cfg(windows)]
             UiCommand::RegisterRightClick => {
                 if unregister_rightclick() {
@@ -140,3 +440,126 @@ impl UiCommand {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drag(position: (u32, u32)) -> UiCommand {
+        UiCommand::Drag {
+            button: "left".to_string(),
+            grid_id: 1,
+            position,
+            modifiers: "".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_coalesce_keyboard_input() {
+        let coalesced = coalesce_ui_commands(vec![
+            UiCommand::Keyboard("a".to_string()),
+            UiCommand::Keyboard("<C-w>".to_string()),
+            UiCommand::FocusLost,
+            UiCommand::Keyboard("b".to_string()),
+        ]);
+
+        assert_eq!(coalesced.len(), 3);
+        match &coalesced[0] {
+            UiCommand::Keyboard(keys) => assert_eq!(keys, "a<C-w>"),
+            ui_command => panic!("Expected keyboard input, got {:?}", ui_command),
+        }
+    }
+
+    #[test]
+    fn test_coalesce_drags() {
+        let coalesced = coalesce_ui_commands(vec![
+            drag((1, 1)),
+            drag((2, 1)),
+            drag((3, 2)),
+            UiCommand::MouseButton {
+                button: "left".to_string(),
+                action: "release".to_string(),
+                grid_id: 1,
+                position: (3, 2),
+                modifiers: "".to_string(),
+            },
+            drag((4, 2)),
+        ]);
+
+        assert_eq!(coalesced.len(), 3);
+        match &coalesced[0] {
+            UiCommand::Drag { position, .. } => assert_eq!(*position, (3, 2)),
+            ui_command => panic!("Expected a drag, got {:?}", ui_command),
+        }
+    }
+
+    #[test]
+    fn test_every_scroll_is_kept() {
+        let scroll = |direction: &str| UiCommand::Scroll {
+            direction: direction.to_string(),
+            grid_id: 1,
+            position: (0, 0),
+            modifiers: "".to_string(),
+        };
+        let coalesced = coalesce_ui_commands(vec![scroll("up"), scroll("up"), scroll("down")]);
+        assert_eq!(coalesced.len(), 3);
+    }
+
+    #[test]
+    fn test_ex_commands_do_not_hold_up_input() {
+        assert!(UiCommand::TabClose(1).runs_ex_command());
+        assert!(UiCommand::FocusGained.runs_ex_command());
+        assert!(!UiCommand::Keyboard("a".to_string()).runs_ex_command());
+    }
+
+    #[test]
+    fn test_paste_chunks() {
+        assert_eq!(paste_chunks("hello"), vec![("hello", -1)]);
+
+        // The leading ascii character shifts the multi byte characters off of the chunk size
+        let text = format!("a{}", "é".repeat(PASTE_CHUNK_SIZE));
+        let chunks = paste_chunks(&text);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(
+            chunks.iter().map(|(_, phase)| *phase).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert!(chunks
+            .iter()
+            .all(|(chunk, _)| chunk.len() <= PASTE_CHUNK_SIZE));
+        assert_eq!(
+            chunks.iter().map(|(chunk, _)| *chunk).collect::<String>(),
+            text
+        );
+    }
+
+    #[test]
+    fn test_file_drop_commands() {
+        let paths = vec!["a\\ b.txt".to_string(), "c.txt".to_string()];
+        assert_eq!(
+            file_drop_commands(FileDropMode::Drop, &paths),
+            vec!["drop a\\ b.txt c.txt"]
+        );
+        assert_eq!(
+            file_drop_commands(FileDropMode::VerticalSplit, &paths),
+            vec!["vsplit a\\ b.txt", "vsplit c.txt"]
+        );
+    }
+
+    #[test]
+    fn test_side_button_keys() {
+        assert_eq!(
+            side_button_keys("x1", "press", ""),
+            Some(String::from("<X1Mouse>"))
+        );
+        assert_eq!(
+            side_button_keys("x2", "release", "S-C-"),
+            Some(String::from("<S-C-X2Release>"))
+        );
+        assert_eq!(
+            side_button_keys("x1", "drag", "M-"),
+            Some(String::from("<M-X1Drag>"))
+        );
+        assert_eq!(side_button_keys("left", "press", ""), None);
+    }
+}
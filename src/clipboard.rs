@@ -0,0 +1,13 @@
+use std::error::Error;
+
+use copypasta::{ClipboardContext, ClipboardProvider};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync + 'static>>;
+
+pub fn get_contents() -> Result<String> {
+    ClipboardContext::new()?.get_contents()
+}
+
+pub fn set_contents(contents: String) -> Result<()> {
+    ClipboardContext::new()?.set_contents(contents)
+}
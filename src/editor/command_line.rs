@@ -0,0 +1,352 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::bridge::StyledContent;
+use crate::settings::{FromValue, Value};
+
+// Grids created by the gui rather than neovim use ids well outside of the range neovim hands out
+// so that they never collide and are always sorted above neovim's own floating windows.
+pub const COMMAND_LINE_GRID_ID: u64 = 1_000_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandLineAnchor {
+    Top,
+    Center,
+    Bottom,
+}
+
+impl FromValue for CommandLineAnchor {
+    fn from_value(&mut self, value: Value) {
+        match value.as_str() {
+            Some("top") => *self = CommandLineAnchor::Top,
+            Some("center") => *self = CommandLineAnchor::Center,
+            Some("bottom") => *self = CommandLineAnchor::Bottom,
+            _ => log::error!(
+                "command_line_position setting expected top, center or bottom, but received: {}",
+                value
+            ),
+        }
+    }
+}
+
+impl From<CommandLineAnchor> for Value {
+    fn from(anchor: CommandLineAnchor) -> Self {
+        match anchor {
+            CommandLineAnchor::Top => "top".into(),
+            CommandLineAnchor::Center => "center".into(),
+            CommandLineAnchor::Bottom => "bottom".into(),
+        }
+    }
+}
+
+#[setting_prefix = "command_line"]
+#[derive(Clone, SettingGroup)]
+pub struct CommandLineSettings {
+    pub position: CommandLineAnchor,
+    pub width: f32,
+}
+
+impl Default for CommandLineSettings {
+    fn default() -> Self {
+        Self {
+            position: CommandLineAnchor::Center,
+            width: 0.6,
+        }
+    }
+}
+
+struct CommandLineLevel {
+    content: StyledContent,
+    position: u64,
+    first_character: String,
+    prompt: String,
+    indent: u64,
+    special_character: Option<(String, bool)>,
+}
+
+// A single grid cell worth of command line text along with the highlight id it should be drawn
+// with.
+pub type CommandLineCell = (String, u64);
+
+pub struct CommandLineLayout {
+    pub width: u64,
+    pub rows: Vec<Vec<CommandLineCell>>,
+    pub cursor: (u64, u64),
+}
+
+pub struct CommandLine {
+    levels: Vec<CommandLineLevel>,
+    block: Vec<StyledContent>,
+}
+
+fn styled_cells(content: &StyledContent) -> Vec<CommandLineCell> {
+    content
+        .iter()
+        .flat_map(|(highlight_id, text)| {
+            text.graphemes(true)
+                .map(move |grapheme| (grapheme.to_string(), *highlight_id))
+        })
+        .collect()
+}
+
+fn unstyled_cells(text: &str) -> Vec<CommandLineCell> {
+    text.graphemes(true)
+        .map(|grapheme| (grapheme.to_string(), 0))
+        .collect()
+}
+
+// Neovim reports the cursor position as a byte offset into the content, but the grid is laid out
+// in graphemes.
+fn grapheme_offset(content: &StyledContent, byte_position: u64) -> usize {
+    let text: String = content.iter().map(|(_, text)| text.as_str()).collect();
+    let byte_position = (byte_position as usize).min(text.len());
+    text.grapheme_indices(true)
+        .take_while(|(index, _)| *index < byte_position)
+        .count()
+}
+
+fn wrap_cells(cells: Vec<CommandLineCell>, width: usize) -> Vec<Vec<CommandLineCell>> {
+    if cells.is_empty() {
+        return vec![Vec::new()];
+    }
+
+    cells.chunks(width).map(|row| row.to_vec()).collect()
+}
+
+impl CommandLine {
+    pub fn new() -> CommandLine {
+        CommandLine {
+            levels: Vec::new(),
+            block: Vec::new(),
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        !self.levels.is_empty() || !self.block.is_empty()
+    }
+
+    pub fn show(
+        &mut self,
+        content: StyledContent,
+        position: u64,
+        first_character: String,
+        prompt: String,
+        indent: u64,
+        level: u64,
+    ) {
+        let new_level = CommandLineLevel {
+            content,
+            position,
+            first_character,
+            prompt,
+            indent,
+            special_character: None,
+        };
+
+        let index = (level.max(1) - 1) as usize;
+        self.levels.truncate(index + 1);
+        if index < self.levels.len() {
+            self.levels[index] = new_level;
+        } else {
+            self.levels.push(new_level);
+        }
+    }
+
+    pub fn set_position(&mut self, position: u64, level: u64) {
+        if let Some(command_line_level) = self.levels.get_mut((level.max(1) - 1) as usize) {
+            command_line_level.position = position;
+            command_line_level.special_character = None;
+        }
+    }
+
+    pub fn set_special_character(&mut self, character: String, shift: bool, level: u64) {
+        if let Some(command_line_level) = self.levels.get_mut((level.max(1) - 1) as usize) {
+            command_line_level.special_character = Some((character, shift));
+        }
+    }
+
+    pub fn hide(&mut self) {
+        self.levels.pop();
+    }
+
+    pub fn show_block(&mut self, lines: Vec<StyledContent>) {
+        self.block = lines;
+    }
+
+    pub fn append_block(&mut self, line: StyledContent) {
+        self.block.push(line);
+    }
+
+    pub fn hide_block(&mut self) {
+        self.block.clear();
+    }
+
+    // The number of columns taken up by the first character, prompt and indent before the text
+    // of the active level begins.
+    pub fn content_offset(&self) -> u64 {
+        self.levels
+            .last()
+            .map(|level| {
+                let prefix = level.first_character.graphemes(true).count()
+                    + level.prompt.graphemes(true).count();
+                prefix as u64 + level.indent
+            })
+            .unwrap_or(0)
+    }
+
+    // Lays out the block lines followed by the active command line level, wrapping any line
+    // longer than the available width. The returned cursor is relative to the top left of the
+    // laid out rows.
+    pub fn layout(&self, preferred_width: u64, max_width: u64) -> CommandLineLayout {
+        let block_lines: Vec<Vec<CommandLineCell>> = self.block.iter().map(styled_cells).collect();
+
+        let (current_line, cursor_index) = if let Some(level) = self.levels.last() {
+            let mut cells = unstyled_cells(&level.first_character);
+            cells.append(&mut unstyled_cells(&level.prompt));
+            cells.extend((0..level.indent).map(|_| (" ".to_string(), 0)));
+
+            let cursor_index = cells.len() + grapheme_offset(&level.content, level.position);
+            cells.append(&mut styled_cells(&level.content));
+
+            if let Some((character, shift)) = &level.special_character {
+                let special_cell = (character.clone(), 0);
+                if *shift || cursor_index >= cells.len() {
+                    cells.insert(cursor_index, special_cell);
+                } else {
+                    cells[cursor_index] = special_cell;
+                }
+            }
+
+            (Some(cells), cursor_index)
+        } else {
+            (None, 0)
+        };
+
+        let longest_line = block_lines
+            .iter()
+            .chain(current_line.iter())
+            .map(|line| line.len() as u64)
+            .max()
+            .unwrap_or(0);
+        let width = preferred_width.max(longest_line + 1).min(max_width).max(1);
+
+        let mut rows: Vec<Vec<CommandLineCell>> = block_lines
+            .into_iter()
+            .flat_map(|line| wrap_cells(line, width as usize))
+            .collect();
+
+        let mut cursor = (0, rows.len().saturating_sub(1) as u64);
+        if let Some(current_line) = current_line {
+            let cursor_row = rows.len() + cursor_index / width as usize;
+            cursor = (cursor_index as u64 % width, cursor_row as u64);
+
+            rows.append(&mut wrap_cells(current_line, width as usize));
+            while rows.len() <= cursor_row {
+                rows.push(Vec::new());
+            }
+        }
+
+        CommandLineLayout {
+            width,
+            rows,
+            cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn show_simple(command_line: &mut CommandLine, text: &str, position: u64) {
+        command_line.show(
+            vec![(0, text.to_string())],
+            position,
+            ":".to_string(),
+            "".to_string(),
+            0,
+            1,
+        );
+    }
+
+    #[test]
+    fn test_layout_single_line() {
+        let mut command_line = CommandLine::new();
+        show_simple(&mut command_line, "write", 5);
+
+        let layout = command_line.layout(20, 80);
+        assert_eq!(layout.width, 20);
+        assert_eq!(layout.rows.len(), 1);
+        assert_eq!(layout.rows[0].len(), 6);
+        assert_eq!(layout.cursor, (6, 0));
+    }
+
+    #[test]
+    fn test_layout_wraps_long_lines() {
+        let mut command_line = CommandLine::new();
+        show_simple(&mut command_line, "abcdefghij", 3);
+
+        let layout = command_line.layout(4, 5);
+        assert_eq!(layout.width, 5);
+        assert_eq!(layout.rows.len(), 3);
+        assert_eq!(layout.cursor, (4, 0));
+    }
+
+    #[test]
+    fn test_layout_block_lines() {
+        let mut command_line = CommandLine::new();
+        command_line.show_block(vec![
+            vec![(0, "function! Foo()".to_string())],
+            vec![(0, "  echo 1".to_string())],
+        ]);
+        command_line.show(
+            vec![(0, "".to_string())],
+            0,
+            "".to_string(),
+            ":".to_string(),
+            2,
+            1,
+        );
+
+        let layout = command_line.layout(10, 80);
+        assert_eq!(layout.rows.len(), 3);
+        assert_eq!(layout.cursor, (3, 2));
+    }
+
+    #[test]
+    fn test_special_character() {
+        let mut command_line = CommandLine::new();
+        show_simple(&mut command_line, "ab", 1);
+
+        command_line.set_special_character("^".to_string(), true, 1);
+        let layout = command_line.layout(10, 80);
+        assert_eq!(layout.rows[0].len(), 4);
+        assert_eq!(layout.rows[0][2].0, "^");
+
+        command_line.set_special_character("^".to_string(), false, 1);
+        let layout = command_line.layout(10, 80);
+        assert_eq!(layout.rows[0].len(), 3);
+        assert_eq!(layout.rows[0][2].0, "^");
+    }
+
+    #[test]
+    fn test_nested_levels() {
+        let mut command_line = CommandLine::new();
+        show_simple(&mut command_line, "first", 0);
+        command_line.show(
+            vec![(0, "1+1".to_string())],
+            3,
+            "=".to_string(),
+            "".to_string(),
+            0,
+            2,
+        );
+        assert_eq!(command_line.layout(1, 80).cursor, (4, 0));
+
+        command_line.hide();
+        assert!(command_line.is_visible());
+        assert_eq!(command_line.layout(1, 80).cursor, (1, 0));
+
+        command_line.hide();
+        assert!(!command_line.is_visible());
+    }
+}
@@ -1,3 +1,4 @@
+mod command_line;
 mod cursor;
 mod draw_command_batcher;
 mod grid;
@@ -13,12 +14,20 @@ use std::thread;
 use crossfire::mpsc::RxUnbounded;
 use log::{error, trace, warn};
 
-use crate::bridge::{EditorMode, GuiOption, RedrawEvent, WindowAnchor};
+use crate::bridge::{
+    EditorMode, GridLineCell, GuiOption, MessageKind, PopupMenuItem, RedrawEvent, StyledContent,
+    TabInfo, WindowAnchor,
+};
 use crate::redraw_scheduler::REDRAW_SCHEDULER;
+use crate::renderer::{BoxDrawingSettings, FontSettings};
+use crate::settings::SETTINGS;
+use crate::window::WindowSettings;
+pub use command_line::CommandLineSettings;
+use command_line::{CommandLine, CommandLineAnchor, COMMAND_LINE_GRID_ID};
 pub use cursor::{Cursor, CursorMode, CursorShape};
 pub use draw_command_batcher::DrawCommandBatcher;
 pub use grid::CharacterGrid;
-pub use style::{Colors, Style};
+pub use style::{blend_alpha, Colors, Style};
 pub use window::*;
 
 pub struct AnchorInfo {
@@ -45,6 +54,9 @@ impl WindowAnchor {
     }
 }
 
+// Styled content with the highlight ids already resolved into styles.
+pub type StyledText = Vec<(String, Option<Arc<Style>>)>;
+
 pub enum DrawCommand {
     CloseWindow(u64),
     Window {
@@ -53,8 +65,46 @@ pub enum DrawCommand {
     },
     UpdateCursor(Cursor),
     FontChanged(String),
+    LineSpaceChanged(u64),
+    FontVariantsChanged(FontSettings),
     DefaultStyleChanged(Style),
     ModeChanged(EditorMode),
+    MessageShow {
+        kind: MessageKind,
+        content: StyledText,
+        replace_last: bool,
+    },
+    MessageClear,
+    MessageShowMode(StyledText),
+    MessageShowCommand(StyledText),
+    MessageRuler(StyledText),
+    MessageHistoryShow(Vec<(MessageKind, StyledText)>),
+    PopupMenuShow {
+        items: Vec<PopupMenuItem>,
+        selected: Option<u64>,
+        anchor_left: f64,
+        anchor_top: f64,
+        styles: PopupMenuStyles,
+    },
+    PopupMenuSelect(Option<u64>),
+    PopupMenuHide,
+    TablineUpdate {
+        current_tab: u64,
+        tabs: Vec<TabInfo>,
+    },
+    ShowTabLine(u64),
+    PopupMenuBlend(u64),
+    Reconnecting(bool),
+}
+
+/// The styles of the highlight groups neovim draws its own popup menu with. Groups the colorscheme
+/// leaves undefined are None and drawn with the default colors.
+#[derive(Clone, Default)]
+pub struct PopupMenuStyles {
+    pub normal: Option<Arc<Style>>,
+    pub selected: Option<Arc<Style>>,
+    pub scrollbar: Option<Arc<Style>>,
+    pub thumb: Option<Arc<Style>>,
 }
 
 pub enum WindowCommand {
@@ -71,8 +121,33 @@ impl fmt::Debug for DrawCommand {
             }
             DrawCommand::UpdateCursor(_) => write!(formatter, "UpdateCursor"),
             DrawCommand::FontChanged(_) => write!(formatter, "FontChanged"),
+            DrawCommand::LineSpaceChanged(_) => write!(formatter, "LineSpaceChanged"),
+            DrawCommand::FontVariantsChanged(_) => write!(formatter, "FontVariantsChanged"),
             DrawCommand::DefaultStyleChanged(_) => write!(formatter, "DefaultStyleChanged"),
             DrawCommand::ModeChanged(_) => write!(formatter, "ModeChanged"),
+            DrawCommand::MessageShow { kind, .. } => write!(formatter, "MessageShow {:?}", kind),
+            DrawCommand::MessageClear => write!(formatter, "MessageClear"),
+            DrawCommand::MessageShowMode(_) => write!(formatter, "MessageShowMode"),
+            DrawCommand::MessageShowCommand(_) => write!(formatter, "MessageShowCommand"),
+            DrawCommand::MessageRuler(_) => write!(formatter, "MessageRuler"),
+            DrawCommand::MessageHistoryShow(_) => write!(formatter, "MessageHistoryShow"),
+            DrawCommand::PopupMenuShow { items, .. } => {
+                write!(formatter, "PopupMenuShow {}", items.len())
+            }
+            DrawCommand::PopupMenuSelect(selected) => {
+                write!(formatter, "PopupMenuSelect {:?}", selected)
+            }
+            DrawCommand::PopupMenuHide => write!(formatter, "PopupMenuHide"),
+            DrawCommand::TablineUpdate { tabs, .. } => {
+                write!(formatter, "TablineUpdate {}", tabs.len())
+            }
+            DrawCommand::ShowTabLine(show_tab_line) => {
+                write!(formatter, "ShowTabLine {}", show_tab_line)
+            }
+            DrawCommand::PopupMenuBlend(blend) => write!(formatter, "PopupMenuBlend {}", blend),
+            DrawCommand::Reconnecting(reconnecting) => {
+                write!(formatter, "Reconnecting {}", reconnecting)
+            }
         }
     }
 }
@@ -81,9 +156,15 @@ pub struct Editor {
     pub windows: HashMap<u64, Window>,
     pub cursor: Cursor,
     pub defined_styles: HashMap<u64, Arc<Style>>,
+    pub highlight_groups: HashMap<String, u64>,
     pub mode_list: Vec<CursorMode>,
+    pub command_line: CommandLine,
     pub draw_command_batcher: Arc<DrawCommandBatcher>,
     pub window_command_sender: Sender<WindowCommand>,
+    saved_cursor_position: Option<(u64, (u64, u64))>,
+    scale_factor: f32,
+    font_variants: FontSettings,
+    box_drawing: bool,
 }
 
 impl Editor {
@@ -95,9 +176,15 @@ impl Editor {
             windows: HashMap::new(),
             cursor: Cursor::new(),
             defined_styles: HashMap::new(),
+            highlight_groups: HashMap::new(),
             mode_list: Vec::new(),
+            command_line: CommandLine::new(),
             draw_command_batcher: Arc::new(DrawCommandBatcher::new(batched_draw_command_sender)),
             window_command_sender,
+            saved_cursor_position: None,
+            scale_factor: 1.0,
+            font_variants: FontSettings::default(),
+            box_drawing: BoxDrawingSettings::default().box_drawing,
         }
     }
 
@@ -138,6 +225,7 @@ impl Editor {
             }
             RedrawEvent::Flush => {
                 trace!("Image flushed");
+                self.update_font_settings();
                 self.send_cursor_info();
                 self.draw_command_batcher.send_batch().ok();
                 REDRAW_SCHEDULER.queue_next_frame();
@@ -150,6 +238,9 @@ impl Editor {
             RedrawEvent::HighlightAttributesDefine { id, style } => {
                 self.defined_styles.insert(id, Arc::new(style));
             }
+            RedrawEvent::HighlightGroupSet { name, id } => {
+                self.highlight_groups.insert(name, id);
+            }
             RedrawEvent::CursorGoto {
                 grid,
                 column: left,
@@ -197,25 +288,36 @@ impl Editor {
             }
             RedrawEvent::WindowPosition {
                 grid,
+                window,
                 start_row,
                 start_column,
                 width,
                 height,
-            } => self.set_window_position(grid, start_column, start_row, width, height),
+            } => {
+                self.set_window_position(grid, start_column, start_row, width, height);
+                self.set_window_handle(grid, window);
+            }
             RedrawEvent::WindowFloatPosition {
                 grid,
+                window,
                 anchor,
                 anchor_grid,
                 anchor_column: anchor_left,
                 anchor_row: anchor_top,
                 ..
-            } => self.set_window_float_position(grid, anchor_grid, anchor, anchor_left, anchor_top),
+            } => {
+                self.set_window_float_position(grid, anchor_grid, anchor, anchor_left, anchor_top);
+                self.set_window_handle(grid, window);
+            }
             RedrawEvent::WindowHide { grid } => {
                 let window = self.windows.get(&grid);
                 if let Some(window) = window {
                     window.hide();
                 }
             }
+            RedrawEvent::WindowExternalPosition { grid, window } => {
+                self.set_window_external_position(grid, window)
+            }
             RedrawEvent::WindowClose { grid } => self.close_window(grid),
             RedrawEvent::MessageSetPosition { grid, row, .. } => {
                 self.set_message_position(grid, row)
@@ -226,10 +328,176 @@ impl Editor {
                 bottom_line,
                 ..
             } => self.send_updated_viewport(grid, top_line, bottom_line),
+            RedrawEvent::CommandLineShow {
+                content,
+                position,
+                first_character,
+                prompt,
+                indent,
+                level,
+            } => {
+                self.command_line
+                    .show(content, position, first_character, prompt, indent, level);
+                self.update_command_line();
+            }
+            RedrawEvent::CommandLinePosition { position, level } => {
+                self.command_line.set_position(position, level);
+                self.update_command_line();
+            }
+            RedrawEvent::CommandLineSpecialCharacter {
+                character,
+                shift,
+                level,
+            } => {
+                self.command_line
+                    .set_special_character(character, shift, level);
+                self.update_command_line();
+            }
+            RedrawEvent::CommandLineHide => {
+                self.command_line.hide();
+                self.update_command_line();
+            }
+            RedrawEvent::CommandLineBlockShow { lines } => {
+                self.command_line.show_block(lines);
+                self.update_command_line();
+            }
+            RedrawEvent::CommandLineBlockAppend { line } => {
+                self.command_line.append_block(line);
+                self.update_command_line();
+            }
+            RedrawEvent::CommandLineBlockHide => {
+                self.command_line.hide_block();
+                self.update_command_line();
+            }
+            RedrawEvent::MessageShow {
+                kind,
+                content,
+                replace_last,
+            } => {
+                let content = self.resolve_styled_content(content);
+                self.draw_command_batcher
+                    .queue(DrawCommand::MessageShow {
+                        kind,
+                        content,
+                        replace_last,
+                    })
+                    .ok();
+            }
+            RedrawEvent::MessageClear => {
+                self.draw_command_batcher
+                    .queue(DrawCommand::MessageClear)
+                    .ok();
+            }
+            RedrawEvent::MessageShowMode { content } => {
+                let content = self.resolve_styled_content(content);
+                self.draw_command_batcher
+                    .queue(DrawCommand::MessageShowMode(content))
+                    .ok();
+            }
+            RedrawEvent::MessageShowCommand { content } => {
+                let content = self.resolve_styled_content(content);
+                self.draw_command_batcher
+                    .queue(DrawCommand::MessageShowCommand(content))
+                    .ok();
+            }
+            RedrawEvent::MessageRuler { content } => {
+                let content = self.resolve_styled_content(content);
+                self.draw_command_batcher
+                    .queue(DrawCommand::MessageRuler(content))
+                    .ok();
+            }
+            RedrawEvent::MessageHistoryShow { entries } => {
+                let entries = entries
+                    .into_iter()
+                    .map(|(kind, content)| (kind, self.resolve_styled_content(content)))
+                    .collect();
+                self.draw_command_batcher
+                    .queue(DrawCommand::MessageHistoryShow(entries))
+                    .ok();
+            }
+            RedrawEvent::PopupMenuShow {
+                items,
+                selected,
+                row,
+                column,
+                grid,
+            } => {
+                let (anchor_left, anchor_top) = self.get_popup_menu_anchor(grid, row, column);
+                let styles = PopupMenuStyles {
+                    normal: self.highlight_group_style("Pmenu"),
+                    selected: self.highlight_group_style("PmenuSel"),
+                    scrollbar: self.highlight_group_style("PmenuSbar"),
+                    thumb: self.highlight_group_style("PmenuThumb"),
+                };
+                self.draw_command_batcher
+                    .queue(DrawCommand::PopupMenuShow {
+                        items,
+                        selected,
+                        anchor_left,
+                        anchor_top,
+                        styles,
+                    })
+                    .ok();
+            }
+            RedrawEvent::PopupMenuSelect { selected } => {
+                self.draw_command_batcher
+                    .queue(DrawCommand::PopupMenuSelect(selected))
+                    .ok();
+            }
+            RedrawEvent::PopupMenuHide => {
+                self.draw_command_batcher
+                    .queue(DrawCommand::PopupMenuHide)
+                    .ok();
+            }
+            RedrawEvent::TablineUpdate { current_tab, tabs } => {
+                self.draw_command_batcher
+                    .queue(DrawCommand::TablineUpdate { current_tab, tabs })
+                    .ok();
+            }
+            RedrawEvent::SettingsChanged => {
+                // Setting a variable doesn't make neovim flush, so the batch is sent right away
+                if self.update_font_settings() {
+                    self.draw_command_batcher.send_batch().ok();
+                    REDRAW_SCHEDULER.queue_next_frame();
+                }
+            }
+            RedrawEvent::UiReattach => self.reset(),
+            RedrawEvent::ConnectionLost => {
+                // Neovim won't send a flush while disconnected, so the batch is sent right away
+                self.draw_command_batcher
+                    .queue(DrawCommand::Reconnecting(true))
+                    .ok();
+                self.draw_command_batcher.send_batch().ok();
+                REDRAW_SCHEDULER.queue_next_frame();
+            }
             _ => {}
         };
     }
 
+    // Throws away everything neovim told us about its grids so that a fresh ui_attach starts from
+    // a clean slate.
+    fn reset(&mut self) {
+        let grids: Vec<u64> = self.windows.keys().copied().collect();
+        for grid in grids {
+            self.close_window(grid);
+        }
+
+        self.defined_styles.clear();
+        self.highlight_groups.clear();
+        self.command_line = CommandLine::new();
+        self.saved_cursor_position = None;
+
+        self.draw_command_batcher
+            .queue(DrawCommand::PopupMenuHide)
+            .ok();
+        self.draw_command_batcher
+            .queue(DrawCommand::MessageClear)
+            .ok();
+        self.draw_command_batcher
+            .queue(DrawCommand::Reconnecting(false))
+            .ok();
+    }
+
     fn close_window(&mut self, grid: u64) {
         if let Some(window) = self.windows.remove(&grid) {
             window.close();
@@ -322,6 +590,21 @@ impl Editor {
         }
     }
 
+    fn set_window_handle(&mut self, grid: u64, window_handle: u64) {
+        if let Some(window) = self.windows.get_mut(&grid) {
+            window.set_window_handle(window_handle);
+        }
+    }
+
+    fn set_window_external_position(&mut self, grid: u64, window_handle: u64) {
+        trace!("External position {}", grid);
+        if let Some(window) = self.windows.get_mut(&grid) {
+            window.set_external(window_handle);
+        } else {
+            error!("Attempted to externalize window that does not exist.");
+        }
+    }
+
     fn set_message_position(&mut self, grid: u64, grid_top: u64) {
         warn!("message position {}", grid);
         let parent_width = self
@@ -360,32 +643,198 @@ impl Editor {
         }
     }
 
+    // Neovim tells which highlight it uses for its builtin ui groups with hl_group_set, so the
+    // parts of the ui drawn by the gui can follow the colorscheme the same way the grid does.
+    fn highlight_group_style(&self, name: &str) -> Option<Arc<Style>> {
+        self.highlight_groups
+            .get(name)
+            .and_then(|id| self.defined_styles.get(id))
+            .cloned()
+    }
+
+    fn resolve_styled_content(&self, content: StyledContent) -> StyledText {
+        content
+            .into_iter()
+            .map(|(highlight_id, text)| {
+                let style = match highlight_id {
+                    0 => None,
+                    highlight_id => self.defined_styles.get(&highlight_id).cloned(),
+                };
+                (text, style)
+            })
+            .collect()
+    }
+
+    fn update_command_line(&mut self) {
+        if !self.command_line.is_visible() {
+            if let Some(window) = self.windows.get(&COMMAND_LINE_GRID_ID) {
+                window.hide();
+            }
+
+            if let Some((grid, (grid_left, grid_top))) = self.saved_cursor_position.take() {
+                self.set_cursor_position(grid, grid_left, grid_top);
+            }
+            return;
+        }
+
+        let settings = SETTINGS.get::<CommandLineSettings>();
+        let (parent_width, parent_height) = self
+            .windows
+            .get(&1)
+            .map(|parent| (parent.get_width(), parent.get_height()))
+            .unwrap_or((1, 1));
+
+        let preferred_width = (parent_width as f32 * settings.width) as u64;
+        let layout = self.command_line.layout(preferred_width, parent_width);
+        let width = layout.width;
+        let height = layout.rows.len() as u64;
+
+        let grid_left = parent_width.saturating_sub(width) / 2;
+        let grid_top = match settings.position {
+            CommandLineAnchor::Top => 1u64.min(parent_height.saturating_sub(height)),
+            CommandLineAnchor::Center => parent_height.saturating_sub(height) / 2,
+            CommandLineAnchor::Bottom => parent_height.saturating_sub(height),
+        };
+
+        let anchor_info = AnchorInfo {
+            anchor_grid_id: 1, // Base Grid
+            anchor_type: WindowAnchor::NorthWest,
+            anchor_left: grid_left as f64,
+            anchor_top: grid_top as f64,
+        };
+
+        if let Some(window) = self.windows.get_mut(&COMMAND_LINE_GRID_ID) {
+            window.position(
+                width,
+                height,
+                Some(anchor_info),
+                grid_left as f64,
+                grid_top as f64,
+            );
+            window.show();
+        } else {
+            let new_window = Window::new(
+                COMMAND_LINE_GRID_ID,
+                width,
+                height,
+                Some(anchor_info),
+                grid_left as f64,
+                grid_top as f64,
+                self.draw_command_batcher.clone(),
+            );
+            self.windows.insert(COMMAND_LINE_GRID_ID, new_window);
+        }
+
+        let defined_styles = &self.defined_styles;
+        if let Some(window) = self.windows.get_mut(&COMMAND_LINE_GRID_ID) {
+            window.clear();
+
+            for (row_index, row) in layout.rows.into_iter().enumerate() {
+                let padding = width.saturating_sub(row.len() as u64);
+                let mut cells: Vec<GridLineCell> = row
+                    .into_iter()
+                    .map(|(text, highlight_id)| GridLineCell {
+                        text,
+                        highlight_id: Some(highlight_id),
+                        repeat: None,
+                    })
+                    .collect();
+
+                // Pad the rest of the row so that the background covers the whole window
+                if padding > 0 {
+                    cells.push(GridLineCell {
+                        text: " ".to_string(),
+                        highlight_id: Some(0),
+                        repeat: Some(padding),
+                    });
+                }
+
+                window.draw_grid_line(row_index as u64, 0, cells, defined_styles);
+            }
+        }
+
+        if self.saved_cursor_position.is_none() {
+            self.saved_cursor_position =
+                Some((self.cursor.parent_window_id, self.cursor.grid_position));
+        }
+
+        let (cursor_left, cursor_top) = layout.cursor;
+        self.set_cursor_position(COMMAND_LINE_GRID_ID, cursor_left, cursor_top);
+    }
+
     fn get_window_top_left(&self, grid: u64) -> Option<(f64, f64)> {
         let window = self.windows.get(&grid)?;
         let window_anchor_info = &window.anchor_info;
 
         match window_anchor_info {
             Some(anchor_info) => {
-                let (parent_anchor_left, parent_anchor_top) =
-                    self.get_window_top_left(anchor_info.anchor_grid_id)?;
-
-                let (anchor_modified_left, anchor_modified_top) =
-                    anchor_info.anchor_type.modified_top_left(
-                        anchor_info.anchor_left,
-                        anchor_info.anchor_top,
-                        window.get_width(),
-                        window.get_height(),
-                    );
-
-                Some((
-                    parent_anchor_left + anchor_modified_left,
-                    parent_anchor_top + anchor_modified_top,
-                ))
+                self.get_anchored_top_left(anchor_info, window.get_width(), window.get_height())
             }
             None => Some(window.get_grid_position()),
         }
     }
 
+    fn get_anchored_top_left(
+        &self,
+        anchor_info: &AnchorInfo,
+        width: u64,
+        height: u64,
+    ) -> Option<(f64, f64)> {
+        let (parent_anchor_left, parent_anchor_top) =
+            self.get_window_top_left(anchor_info.anchor_grid_id)?;
+
+        let (anchor_modified_left, anchor_modified_top) =
+            anchor_info.anchor_type.modified_top_left(
+                anchor_info.anchor_left,
+                anchor_info.anchor_top,
+                width,
+                height,
+            );
+
+        Some((
+            parent_anchor_left + anchor_modified_left,
+            parent_anchor_top + anchor_modified_top,
+        ))
+    }
+
+    // Returns the position of the cell the popup menu is attached to in base grid coordinates.
+    // Menus for the command line report no grid and a column relative to the command line text.
+    fn get_popup_menu_anchor(&self, grid: Option<u64>, row: u64, column: u64) -> (f64, f64) {
+        let (anchor_grid_id, anchor_left, anchor_top) = match grid {
+            Some(grid) => (grid, column, row),
+            None if self.command_line.is_visible() => {
+                let command_line_height = self
+                    .windows
+                    .get(&COMMAND_LINE_GRID_ID)
+                    .map(|window| window.get_height())
+                    .unwrap_or(1);
+                (
+                    COMMAND_LINE_GRID_ID,
+                    self.command_line.content_offset() + column,
+                    command_line_height.saturating_sub(1),
+                )
+            }
+            None => {
+                let parent_height = self
+                    .windows
+                    .get(&1)
+                    .map(|parent| parent.get_height())
+                    .unwrap_or(1);
+                (1, column, parent_height.saturating_sub(1))
+            }
+        };
+
+        let anchor_info = AnchorInfo {
+            anchor_grid_id,
+            anchor_type: WindowAnchor::NorthWest,
+            anchor_left: anchor_left as f64,
+            anchor_top: anchor_top as f64,
+        };
+
+        self.get_anchored_top_left(&anchor_info, 1, 1)
+            .unwrap_or((anchor_left as f64, anchor_top as f64))
+    }
+
     fn set_cursor_position(&mut self, grid: u64, grid_left: u64, grid_top: u64) {
         self.cursor.parent_window_id = grid;
         self.cursor.grid_position = (grid_left, grid_top);
@@ -408,14 +857,66 @@ impl Editor {
 
     fn set_option(&mut self, gui_option: GuiOption) {
         trace!("Option set {:?}", &gui_option);
-        if let GuiOption::GuiFont(guifont) = gui_option {
+        match gui_option {
+            GuiOption::GuiFont(guifont) => {
+                self.draw_command_batcher
+                    .queue(DrawCommand::FontChanged(guifont))
+                    .ok();
+                for window in self.windows.values() {
+                    window.redraw();
+                }
+            }
+            GuiOption::LineSpace(line_space) => {
+                self.draw_command_batcher
+                    .queue(DrawCommand::LineSpaceChanged(line_space))
+                    .ok();
+                for window in self.windows.values() {
+                    window.redraw();
+                }
+            }
+            GuiOption::Pumblend(blend) => {
+                self.draw_command_batcher
+                    .queue(DrawCommand::PopupMenuBlend(blend))
+                    .ok();
+            }
+            GuiOption::ShowTabLine(show_tab_line) => {
+                self.draw_command_batcher
+                    .queue(DrawCommand::ShowTabLine(show_tab_line))
+                    .ok();
+            }
+            _ => {}
+        }
+    }
+
+    // The font and box drawing settings which live in neovide variables rather than in options.
+    // Every cell is drawn again whenever they change, the same as after a guifont change. The
+    // renderer applies the scale factor itself, so the editor only redraws for it.
+    fn update_font_settings(&mut self) -> bool {
+        let scale_factor = SETTINGS.get::<WindowSettings>().scale_factor;
+        let font_variants = SETTINGS.get::<FontSettings>();
+        let scale_factor_changed = (self.scale_factor - scale_factor).abs() > std::f32::EPSILON;
+        let font_variants_changed = self.font_variants != font_variants;
+        // The renderer reads the box drawing setting itself, so a redraw is all it needs
+        let box_drawing = SETTINGS.get::<BoxDrawingSettings>().box_drawing;
+        let box_drawing_changed = self.box_drawing != box_drawing;
+        self.box_drawing = box_drawing;
+
+        self.scale_factor = scale_factor;
+
+        if font_variants_changed {
+            self.font_variants = font_variants.clone();
             self.draw_command_batcher
-                .queue(DrawCommand::FontChanged(guifont))
+                .queue(DrawCommand::FontVariantsChanged(font_variants))
                 .ok();
+        }
+
+        let updated = scale_factor_changed || font_variants_changed || box_drawing_changed;
+        if updated {
             for window in self.windows.values() {
                 window.redraw();
             }
         }
+        updated
     }
 
     fn send_updated_viewport(&mut self, grid: u64, top_line: f64, bottom_line: f64) {
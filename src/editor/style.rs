@@ -26,6 +26,11 @@ pub struct Style {
     pub blend: u8,
 }
 
+// Converts neovim's blend level, where 0 is opaque and 100 fully transparent, into an alpha value.
+pub fn blend_alpha(blend: u8) -> u8 {
+    (255 * (100 - blend.min(100) as u32) / 100) as u8
+}
+
 impl Style {
     pub fn foreground(&self, default_colors: &Colors) -> Color4f {
         if self.reverse {
@@ -61,6 +66,10 @@ impl Style {
             .clone()
             .unwrap_or_else(|| default_colors.special.clone().unwrap())
     }
+
+    pub fn background_alpha(&self) -> u8 {
+        blend_alpha(self.blend)
+    }
 }
 
 #[cfg(test)]
@@ -155,4 +164,16 @@ mod tests {
             DEFAULT_COLORS.special.clone().unwrap()
         );
     }
+
+    #[test]
+    fn test_background_alpha() {
+        let mut style = Style::new(COLORS);
+        assert_eq!(style.background_alpha(), 255);
+
+        style.blend = 50;
+        assert_eq!(style.background_alpha(), 127);
+
+        style.blend = 100;
+        assert_eq!(style.background_alpha(), 0);
+    }
 }
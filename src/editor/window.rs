@@ -18,6 +18,12 @@ pub enum WindowDrawCommand {
         width: u64,
         height: u64,
         floating: bool,
+        window_handle: Option<u64>,
+    },
+    External {
+        window_handle: u64,
+        width: u64,
+        height: u64,
     },
     Cell {
         text: String,
@@ -56,6 +62,9 @@ impl fmt::Debug for WindowDrawCommand {
                 "Position {{ left: {}, right: {} }}",
                 grid_left, grid_top
             ),
+            WindowDrawCommand::External { window_handle, .. } => {
+                write!(formatter, "External {{ window: {} }}", window_handle)
+            }
             WindowDrawCommand::Cell { .. } => write!(formatter, "Cell"),
             WindowDrawCommand::Scroll { .. } => write!(formatter, "Scroll"),
             WindowDrawCommand::Clear => write!(formatter, "Clear"),
@@ -82,6 +91,8 @@ pub struct Window {
 
     grid_left: f64,
     grid_top: f64,
+    window_handle: Option<u64>,
+    external_window: Option<u64>,
 
     draw_command_batcher: Arc<DrawCommandBatcher>,
 }
@@ -102,6 +113,8 @@ impl Window {
             anchor_info,
             grid_left,
             grid_top,
+            window_handle: None,
+            external_window: None,
             draw_command_batcher,
         };
         window.send_updated_position();
@@ -118,13 +131,23 @@ impl Window {
     }
 
     fn send_updated_position(&self) {
-        self.send_command(WindowDrawCommand::Position {
-            grid_left: self.grid_left,
-            grid_top: self.grid_top,
-            width: self.grid.width,
-            height: self.grid.height,
-            floating: self.anchor_info.is_some(),
-        });
+        // External windows live in their own os window, so only their size is relevant
+        if let Some(window_handle) = self.external_window {
+            self.send_command(WindowDrawCommand::External {
+                window_handle,
+                width: self.grid.width,
+                height: self.grid.height,
+            });
+        } else {
+            self.send_command(WindowDrawCommand::Position {
+                grid_left: self.grid_left,
+                grid_top: self.grid_top,
+                width: self.grid.width,
+                height: self.grid.height,
+                floating: self.anchor_info.is_some(),
+                window_handle: self.window_handle,
+            });
+        }
     }
 
     pub fn get_cursor_character(&self, window_left: u64, window_top: u64) -> (String, bool) {
@@ -165,6 +188,23 @@ impl Window {
         self.anchor_info = anchor_info;
         self.grid_left = grid_left;
         self.grid_top = grid_top;
+        self.external_window = None;
+        self.send_updated_position();
+        self.redraw();
+    }
+
+    // Grids are only identified by their grid id in redraw events, but commands sent back to
+    // neovim need the window handle which owns the grid.
+    pub fn set_window_handle(&mut self, window_handle: u64) {
+        if self.window_handle != Some(window_handle) {
+            self.window_handle = Some(window_handle);
+            self.send_updated_position();
+        }
+    }
+
+    pub fn set_external(&mut self, window_handle: u64) {
+        self.anchor_info = None;
+        self.external_window = Some(window_handle);
         self.send_updated_position();
         self.redraw();
     }
@@ -226,7 +266,8 @@ impl Window {
     ) -> Option<u64> {
         let row = self.grid.row(row_index).unwrap();
 
-        let (_, style) = &row[current_start as usize].as_ref()?;
+        // Malformed grid lines may start past the end of the row
+        let (_, style) = &row.get(current_start as usize)?.as_ref()?;
 
         let mut draw_command_start_index = current_start;
         if current_start == line_start {
@@ -4,6 +4,7 @@
 extern crate neovide_derive;
 
 mod bridge;
+mod clipboard;
 mod editor;
 mod error_handling;
 mod redraw_scheduler;
@@ -24,8 +25,11 @@ use std::sync::{atomic::AtomicBool, mpsc::channel, Arc};
 use crossfire::mpsc::unbounded_future;
 
 use bridge::start_bridge;
-use editor::start_editor;
-use renderer::{cursor_renderer::CursorSettings, RendererSettings};
+use editor::{start_editor, CommandLineSettings};
+use renderer::{
+    cursor_renderer::CursorSettings, BoxDrawingSettings, FontSettings, MessageSettings,
+    PopupMenuSettings, RendererSettings,
+};
 use window::{create_window, window_geometry, KeyboardSettings, WindowSettings};
 use windows_utils::attach_parent_console;
 
@@ -156,7 +160,12 @@ fn main() {
     WindowSettings::register();
     redraw_scheduler::RedrawSettings::register();
     RendererSettings::register();
+    BoxDrawingSettings::register();
+    FontSettings::register();
     CursorSettings::register();
+    CommandLineSettings::register();
+    MessageSettings::register();
+    PopupMenuSettings::register();
 
     let running = Arc::new(AtomicBool::new(true));
 
@@ -0,0 +1,531 @@
+use skulpin::skia_safe::{Canvas, Color, Paint, PaintStyle, Path, Rect};
+
+// Line weights of the four arms of a box drawing character, listed as up, right, down and left
+const N: u8 = 0;
+const L: u8 = 1;
+const H: u8 = 2;
+const D: u8 = 3;
+
+const UP: usize = 0;
+const RIGHT: usize = 1;
+const DOWN: usize = 2;
+const LEFT: usize = 3;
+
+/// Whether the character is drawn as a path filling its cell rather than with the font. These are
+/// the box drawing and block element blocks and the powerline separators, which only line up
+/// between cells when they match the cell size exactly.
+pub fn is_procedural_glyph(character: char) -> bool {
+    matches!(character as u32, 0x2500..=0x259F | 0xE0B0..=0xE0BF)
+}
+
+pub fn draw_procedural_glyph(canvas: &mut Canvas, character: char, cell: Rect, color: Color) {
+    let mut paint = Paint::default();
+    paint.set_color(color);
+    paint.set_anti_alias(false);
+
+    let code_point = character as u32;
+    if let Some(weights) = line_weights(code_point) {
+        draw_lines(canvas, &paint, cell, weights);
+    } else if let Some((weight, vertical, count)) = dashed_line(code_point) {
+        draw_dashes(canvas, &paint, cell, weight, vertical, count);
+    } else {
+        match code_point {
+            0x256D..=0x2573 => draw_arc_or_diagonal(canvas, &mut paint, cell, code_point),
+            0x2580..=0x259F => draw_block(canvas, &mut paint, cell, code_point),
+            _ => draw_powerline(canvas, &mut paint, cell, code_point),
+        }
+    }
+}
+
+fn line_weights(code_point: u32) -> Option<[u8; 4]> {
+    let weights = match code_point {
+        0x2500 => [N, L, N, L],
+        0x2501 => [N, H, N, H],
+        0x2502 => [L, N, L, N],
+        0x2503 => [H, N, H, N],
+        0x250C => [N, L, L, N],
+        0x250D => [N, H, L, N],
+        0x250E => [N, L, H, N],
+        0x250F => [N, H, H, N],
+        0x2510 => [N, N, L, L],
+        0x2511 => [N, N, L, H],
+        0x2512 => [N, N, H, L],
+        0x2513 => [N, N, H, H],
+        0x2514 => [L, L, N, N],
+        0x2515 => [L, H, N, N],
+        0x2516 => [H, L, N, N],
+        0x2517 => [H, H, N, N],
+        0x2518 => [L, N, N, L],
+        0x2519 => [L, N, N, H],
+        0x251A => [H, N, N, L],
+        0x251B => [H, N, N, H],
+        0x251C => [L, L, L, N],
+        0x251D => [L, H, L, N],
+        0x251E => [H, L, L, N],
+        0x251F => [L, L, H, N],
+        0x2520 => [H, L, H, N],
+        0x2521 => [H, H, L, N],
+        0x2522 => [L, H, H, N],
+        0x2523 => [H, H, H, N],
+        0x2524 => [L, N, L, L],
+        0x2525 => [L, N, L, H],
+        0x2526 => [H, N, L, L],
+        0x2527 => [L, N, H, L],
+        0x2528 => [H, N, H, L],
+        0x2529 => [H, N, L, H],
+        0x252A => [L, N, H, H],
+        0x252B => [H, N, H, H],
+        0x252C => [N, L, L, L],
+        0x252D => [N, L, L, H],
+        0x252E => [N, H, L, L],
+        0x252F => [N, H, L, H],
+        0x2530 => [N, L, H, L],
+        0x2531 => [N, L, H, H],
+        0x2532 => [N, H, H, L],
+        0x2533 => [N, H, H, H],
+        0x2534 => [L, L, N, L],
+        0x2535 => [L, L, N, H],
+        0x2536 => [L, H, N, L],
+        0x2537 => [L, H, N, H],
+        0x2538 => [H, L, N, L],
+        0x2539 => [H, L, N, H],
+        0x253A => [H, H, N, L],
+        0x253B => [H, H, N, H],
+        0x253C => [L, L, L, L],
+        0x253D => [L, L, L, H],
+        0x253E => [L, H, L, L],
+        0x253F => [L, H, L, H],
+        0x2540 => [H, L, L, L],
+        0x2541 => [L, L, H, L],
+        0x2542 => [H, L, H, L],
+        0x2543 => [H, L, L, H],
+        0x2544 => [H, H, L, L],
+        0x2545 => [L, L, H, H],
+        0x2546 => [L, H, H, L],
+        0x2547 => [H, H, L, H],
+        0x2548 => [L, H, H, H],
+        0x2549 => [H, L, H, H],
+        0x254A => [H, H, H, L],
+        0x254B => [H, H, H, H],
+        0x2550 => [N, D, N, D],
+        0x2551 => [D, N, D, N],
+        0x2552 => [N, D, L, N],
+        0x2553 => [N, L, D, N],
+        0x2554 => [N, D, D, N],
+        0x2555 => [N, N, L, D],
+        0x2556 => [N, N, D, L],
+        0x2557 => [N, N, D, D],
+        0x2558 => [L, D, N, N],
+        0x2559 => [D, L, N, N],
+        0x255A => [D, D, N, N],
+        0x255B => [L, N, N, D],
+        0x255C => [D, N, N, L],
+        0x255D => [D, N, N, D],
+        0x255E => [L, D, L, N],
+        0x255F => [D, L, D, N],
+        0x2560 => [D, D, D, N],
+        0x2561 => [L, N, L, D],
+        0x2562 => [D, N, D, L],
+        0x2563 => [D, N, D, D],
+        0x2564 => [N, D, L, D],
+        0x2565 => [N, L, D, L],
+        0x2566 => [N, D, D, D],
+        0x2567 => [L, D, N, D],
+        0x2568 => [D, L, N, L],
+        0x2569 => [D, D, N, D],
+        0x256A => [L, D, L, D],
+        0x256B => [D, L, D, L],
+        0x256C => [D, D, D, D],
+        0x2574 => [N, N, N, L],
+        0x2575 => [L, N, N, N],
+        0x2576 => [N, L, N, N],
+        0x2577 => [N, N, L, N],
+        0x2578 => [N, N, N, H],
+        0x2579 => [H, N, N, N],
+        0x257A => [N, H, N, N],
+        0x257B => [N, N, H, N],
+        0x257C => [N, H, N, L],
+        0x257D => [L, N, H, N],
+        0x257E => [N, L, N, H],
+        0x257F => [H, N, L, N],
+        _ => return None,
+    };
+    Some(weights)
+}
+
+// The weight, whether the line is vertical and the number of dashes
+fn dashed_line(code_point: u32) -> Option<(u8, bool, u32)> {
+    match code_point {
+        0x2504 => Some((L, false, 3)),
+        0x2505 => Some((H, false, 3)),
+        0x2506 => Some((L, true, 3)),
+        0x2507 => Some((H, true, 3)),
+        0x2508 => Some((L, false, 4)),
+        0x2509 => Some((H, false, 4)),
+        0x250A => Some((L, true, 4)),
+        0x250B => Some((H, true, 4)),
+        0x254C => Some((L, false, 2)),
+        0x254D => Some((H, false, 2)),
+        0x254E => Some((L, true, 2)),
+        0x254F => Some((H, true, 2)),
+        _ => None,
+    }
+}
+
+fn light_thickness(cell: Rect) -> f32 {
+    (cell.width() / 8.0).round().max(1.0)
+}
+
+fn thickness(cell: Rect, weight: u8) -> f32 {
+    match weight {
+        H => light_thickness(cell) * 2.0,
+        _ => light_thickness(cell),
+    }
+}
+
+// Lines are filled rectangles snapped to whole units so that they stay crisp and line up with the
+// same line in the neighbouring cells.
+fn horizontal_line(left: f32, right: f32, center: f32, thickness: f32) -> Rect {
+    let top = (center - thickness / 2.0).round();
+    Rect::new(left, top, right, top + thickness)
+}
+
+fn vertical_line(top: f32, bottom: f32, center: f32, thickness: f32) -> Rect {
+    let left = (center - thickness / 2.0).round();
+    Rect::new(left, top, left + thickness, bottom)
+}
+
+fn draw_lines(canvas: &mut Canvas, paint: &Paint, cell: Rect, weights: [u8; 4]) {
+    let center_x = cell.center_x();
+    let center_y = cell.center_y();
+    let light = light_thickness(cell);
+    // Distance from the center to each line of a double line
+    let gap = light;
+
+    for (arm, &weight) in weights.iter().enumerate() {
+        if weight == N {
+            continue;
+        }
+
+        let horizontal = arm == LEFT || arm == RIGHT;
+        let (before, after) = if horizontal {
+            (weights[UP], weights[DOWN])
+        } else {
+            (weights[LEFT], weights[RIGHT])
+        };
+
+        // Each line is drawn at an offset across the arm, and starts at a distance from the center
+        // measured towards the edge the arm runs to
+        let mut lines = Vec::new();
+        if weight == D {
+            for &(offset, side, other_side) in [(-gap, before, after), (gap, after, before)].iter()
+            {
+                let start = match (side, other_side) {
+                    // The inner line of a corner stops at the double line crossing it
+                    (D, _) => gap,
+                    (N, D) => -(gap + light / 2.0),
+                    _ => 0.0,
+                };
+                lines.push((offset, start, light));
+            }
+        } else {
+            // Single lines reach across to the far line of a crossing double line and otherwise
+            // overlap the crossing line enough to join it without a gap
+            let start = [before, after]
+                .iter()
+                .map(|&crossing| match crossing {
+                    N => 0.0,
+                    D => -(gap + light / 2.0),
+                    crossing => -thickness(cell, crossing) / 2.0,
+                })
+                .fold(0.0, f32::min);
+            lines.push((0.0, start, thickness(cell, weight)));
+        }
+
+        for (offset, start, line_thickness) in lines.into_iter() {
+            let rect = match arm {
+                UP => vertical_line(
+                    cell.top,
+                    center_y - start,
+                    center_x + offset,
+                    line_thickness,
+                ),
+                RIGHT => horizontal_line(
+                    center_x + start,
+                    cell.right,
+                    center_y + offset,
+                    line_thickness,
+                ),
+                DOWN => vertical_line(
+                    center_y + start,
+                    cell.bottom,
+                    center_x + offset,
+                    line_thickness,
+                ),
+                _ => horizontal_line(
+                    cell.left,
+                    center_x - start,
+                    center_y + offset,
+                    line_thickness,
+                ),
+            };
+            canvas.draw_rect(rect, paint);
+        }
+    }
+}
+
+fn draw_dashes(
+    canvas: &mut Canvas,
+    paint: &Paint,
+    cell: Rect,
+    weight: u8,
+    vertical: bool,
+    count: u32,
+) {
+    let line_thickness = thickness(cell, weight);
+    let length = if vertical {
+        cell.height()
+    } else {
+        cell.width()
+    };
+    // Each dash is followed by a gap half its length, split around the dash so that dashes in
+    // neighbouring cells are evenly spaced too
+    let step = length / count as f32;
+    let dash = step * 2.0 / 3.0;
+
+    for index in 0..count {
+        let start = index as f32 * step + (step - dash) / 2.0;
+        let rect = if vertical {
+            vertical_line(
+                cell.top + start,
+                cell.top + start + dash,
+                cell.center_x(),
+                line_thickness,
+            )
+        } else {
+            horizontal_line(
+                cell.left + start,
+                cell.left + start + dash,
+                cell.center_y(),
+                line_thickness,
+            )
+        };
+        canvas.draw_rect(rect, paint);
+    }
+}
+
+fn draw_arc_or_diagonal(canvas: &mut Canvas, paint: &mut Paint, cell: Rect, code_point: u32) {
+    let center_x = cell.center_x();
+    let center_y = cell.center_y();
+    // Arcs bend with a radius of half the cell width, so they meet straight lines in the next cell
+    let radius = cell.width() / 2.0;
+
+    paint.set_anti_alias(true);
+    paint.set_style(PaintStyle::Stroke);
+    paint.set_stroke_width(light_thickness(cell));
+
+    let mut path = Path::new();
+    match code_point {
+        0x256D => {
+            path.move_to((center_x, cell.bottom));
+            path.line_to((center_x, center_y + radius));
+            path.quad_to((center_x, center_y), (center_x + radius, center_y));
+            path.line_to((cell.right, center_y));
+        }
+        0x256E => {
+            path.move_to((center_x, cell.bottom));
+            path.line_to((center_x, center_y + radius));
+            path.quad_to((center_x, center_y), (center_x - radius, center_y));
+            path.line_to((cell.left, center_y));
+        }
+        0x256F => {
+            path.move_to((center_x, cell.top));
+            path.line_to((center_x, center_y - radius));
+            path.quad_to((center_x, center_y), (center_x - radius, center_y));
+            path.line_to((cell.left, center_y));
+        }
+        0x2570 => {
+            path.move_to((center_x, cell.top));
+            path.line_to((center_x, center_y - radius));
+            path.quad_to((center_x, center_y), (center_x + radius, center_y));
+            path.line_to((cell.right, center_y));
+        }
+        _ => {
+            if code_point != 0x2572 {
+                path.move_to((cell.right, cell.top));
+                path.line_to((cell.left, cell.bottom));
+            }
+            if code_point != 0x2571 {
+                path.move_to((cell.left, cell.top));
+                path.line_to((cell.right, cell.bottom));
+            }
+        }
+    }
+    canvas.draw_path(&path, paint);
+}
+
+fn draw_block(canvas: &mut Canvas, paint: &mut Paint, cell: Rect, code_point: u32) {
+    let Rect {
+        left,
+        top,
+        right,
+        bottom,
+    } = cell;
+    let width = cell.width();
+    let height = cell.height();
+    let center_x = cell.center_x().round();
+    let center_y = cell.center_y().round();
+    let eighth_from_bottom = |eighths: u32| (bottom - height * eighths as f32 / 8.0).round();
+    let eighth_from_left = |eighths: u32| (left + width * eighths as f32 / 8.0).round();
+
+    let upper_left = Rect::new(left, top, center_x, center_y);
+    let upper_right = Rect::new(center_x, top, right, center_y);
+    let lower_left = Rect::new(left, center_y, center_x, bottom);
+    let lower_right = Rect::new(center_x, center_y, right, bottom);
+
+    let rects = match code_point {
+        0x2580 => vec![Rect::new(left, top, right, center_y)],
+        0x2581..=0x2588 => vec![Rect::new(
+            left,
+            eighth_from_bottom(code_point - 0x2580),
+            right,
+            bottom,
+        )],
+        0x2589..=0x258F => vec![Rect::new(
+            left,
+            top,
+            eighth_from_left(0x2590 - code_point),
+            bottom,
+        )],
+        0x2590 => vec![Rect::new(center_x, top, right, bottom)],
+        0x2591..=0x2593 => {
+            // Shades are drawn as translucent blocks instead of a dot pattern
+            let alpha = paint.alpha() as u32 * (code_point - 0x2590) / 4;
+            paint.set_alpha(alpha as u8);
+            vec![cell]
+        }
+        0x2594 => vec![Rect::new(left, top, right, eighth_from_bottom(7))],
+        0x2595 => vec![Rect::new(eighth_from_left(7), top, right, bottom)],
+        0x2596 => vec![lower_left],
+        0x2597 => vec![lower_right],
+        0x2598 => vec![upper_left],
+        0x2599 => vec![upper_left, lower_left, lower_right],
+        0x259A => vec![upper_left, lower_right],
+        0x259B => vec![upper_left, upper_right, lower_left],
+        0x259C => vec![upper_left, upper_right, lower_right],
+        0x259D => vec![upper_right],
+        0x259E => vec![upper_right, lower_left],
+        _ => vec![upper_right, lower_left, lower_right],
+    };
+
+    for rect in rects.into_iter() {
+        canvas.draw_rect(rect, paint);
+    }
+}
+
+fn draw_powerline(canvas: &mut Canvas, paint: &mut Paint, cell: Rect, code_point: u32) {
+    let Rect {
+        left,
+        top,
+        right,
+        bottom,
+    } = cell;
+    let center_y = cell.center_y();
+
+    paint.set_anti_alias(true);
+    // Odd code points are the thin outlines of the solid shape before them
+    let solid = code_point % 2 == 0;
+    if solid {
+        paint.set_style(PaintStyle::Fill);
+    } else {
+        paint.set_style(PaintStyle::Stroke);
+        paint.set_stroke_width(light_thickness(cell));
+    }
+
+    let mut path = Path::new();
+    match code_point {
+        0xE0B0 | 0xE0B1 => {
+            path.move_to((left, top));
+            path.line_to((right, center_y));
+            path.line_to((left, bottom));
+        }
+        0xE0B2 | 0xE0B3 => {
+            path.move_to((right, top));
+            path.line_to((left, center_y));
+            path.line_to((right, bottom));
+        }
+        0xE0B4 | 0xE0B5 => {
+            let oval = Rect::new(left - cell.width(), top, right, bottom);
+            path.add_arc(oval, -90.0, 180.0);
+        }
+        0xE0B6 | 0xE0B7 => {
+            let oval = Rect::new(left, top, right + cell.width(), bottom);
+            path.add_arc(oval, 90.0, 180.0);
+        }
+        0xE0B8 => {
+            path.move_to((left, top));
+            path.line_to((right, bottom));
+            path.line_to((left, bottom));
+        }
+        0xE0BA => {
+            path.move_to((right, top));
+            path.line_to((right, bottom));
+            path.line_to((left, bottom));
+        }
+        0xE0BC => {
+            path.move_to((left, top));
+            path.line_to((right, top));
+            path.line_to((left, bottom));
+        }
+        0xE0BE => {
+            path.move_to((left, top));
+            path.line_to((right, top));
+            path.line_to((right, bottom));
+        }
+        0xE0B9 | 0xE0BF => {
+            path.move_to((left, top));
+            path.line_to((right, bottom));
+        }
+        _ => {
+            path.move_to((left, bottom));
+            path.line_to((right, top));
+        }
+    }
+
+    if solid {
+        path.close();
+    }
+    canvas.draw_path(&path, paint);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_procedural_glyph() {
+        assert!(is_procedural_glyph('─'));
+        assert!(is_procedural_glyph('╬'));
+        assert!(is_procedural_glyph('█'));
+        assert!(is_procedural_glyph('\u{E0B0}'));
+        assert!(!is_procedural_glyph('a'));
+        assert!(!is_procedural_glyph('\u{E0A0}'));
+    }
+
+    #[test]
+    fn test_line_weights_mirror() {
+        // Mirroring a character left to right swaps the weights of its left and right arms
+        let mirrored = [
+            (0x250C, 0x2510),
+            (0x2515, 0x2519),
+            (0x251D, 0x2525),
+            (0x2554, 0x2557),
+            (0x2560, 0x2563),
+            (0x257C, 0x257E),
+        ];
+        for &(character, mirror) in mirrored.iter() {
+            let [up, right, down, left] = line_weights(character).unwrap();
+            assert_eq!(line_weights(mirror), Some([up, left, down, right]));
+        }
+    }
+}
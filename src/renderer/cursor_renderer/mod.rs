@@ -208,6 +208,15 @@ impl CursorRenderer {
             .collect::<Vec<Corner>>();
     }
 
+    pub fn grid_id(&self) -> u64 {
+        self.cursor.parent_window_id
+    }
+
+    // Top left of the cell the cursor is moving to, relative to the grid area.
+    pub fn destination(&self) -> Point {
+        self.destination
+    }
+
     pub fn update_cursor_destination(
         &mut self,
         font_width: f32,
@@ -1,6 +1,12 @@
 use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
 
+use font_kit::handle::Handle;
 use font_kit::metrics::Metrics;
+use harfbuzz_rs::{
+    shape as harfbuzz_shape, Blob, Face, Feature, Font as HarfbuzzFont, Owned, Tag, UnicodeBuffer,
+};
 use log::{trace, warn};
 use lru::LruCache;
 use skribo::{FontCollection, FontRef as SkriboFont, LayoutSession, TextStyle};
@@ -25,31 +31,39 @@ struct ShapeKey {
     pub text: String,
     pub bold: bool,
     pub italic: bool,
+    pub features: Arc<[FontFeature]>,
 }
 
 struct FontSet {
     normal: FontCollection,
     bold: FontCollection,
     italic: FontCollection,
+    bold_italic: FontCollection,
 }
 
 impl FontSet {
-    fn new(fallback_list: &[String], loader: &mut FontLoader) -> FontSet {
+    fn new(options: &FontOptions, loader: &mut FontLoader) -> FontSet {
+        let mut build_collection = |bold, italic| {
+            loader.build_collection_by_font_name(
+                &options.variant_fallback_list(bold, italic),
+                build_properties(bold, italic),
+            )
+        };
+
         FontSet {
-            normal: loader
-                .build_collection_by_font_name(fallback_list, build_properties(false, false)),
-            bold: loader
-                .build_collection_by_font_name(fallback_list, build_properties(true, false)),
-            italic: loader
-                .build_collection_by_font_name(fallback_list, build_properties(false, true)),
+            normal: build_collection(false, false),
+            bold: build_collection(true, false),
+            italic: build_collection(false, true),
+            bold_italic: build_collection(true, true),
         }
     }
 
     fn get(&self, bold: bool, italic: bool) -> &FontCollection {
         match (bold, italic) {
-            (true, _) => &self.bold,
             (false, false) => &self.normal,
+            (true, false) => &self.bold,
             (false, true) => &self.italic,
+            (true, true) => &self.bold_italic,
         }
     }
 }
@@ -59,21 +73,29 @@ pub struct CachingShaper {
     font_set: FontSet,
     font_loader: FontLoader,
     font_cache: LruCache<String, SkiaFont>,
+    harfbuzz_font_cache: LruCache<String, Owned<HarfbuzzFont<'static>>>,
+    features: Arc<[FontFeature]>,
+    harfbuzz_features: Vec<Feature>,
     blob_cache: LruCache<ShapeKey, Vec<TextBlob>>,
+    line_space: f32,
 }
 
 impl CachingShaper {
     pub fn new() -> CachingShaper {
         let options = FontOptions::new(String::from(SYSTEM_DEFAULT_FONT), DEFAULT_FONT_SIZE);
         let mut loader = FontLoader::new();
-        let font_set = FontSet::new(&options.fallback_list, &mut loader);
+        let font_set = FontSet::new(&options, &mut loader);
 
         CachingShaper {
             options,
             font_set,
             font_loader: loader,
             font_cache: LruCache::new(10),
+            harfbuzz_font_cache: LruCache::new(10),
+            features: Arc::from(Vec::new()),
+            harfbuzz_features: Vec::new(),
             blob_cache: LruCache::new(10000),
+            line_space: 0.0,
         }
     }
 
@@ -102,9 +124,16 @@ impl CachingShaper {
         let style = TextStyle {
             size: self.options.size,
         };
-        let session = LayoutSession::create(text, &style, &self.font_set.get(bold, italic));
         let metrics = self.metrics();
         let ascent = metrics.ascent * self.options.size / metrics.units_per_em as f32;
+        // Extra line space is split evenly above and below the text to keep it centered in the row
+        let baseline = ascent + self.line_space / 2.0;
+
+        if !self.options.features.is_empty() {
+            return self.shape_with_features(text, bold, italic, baseline);
+        }
+
+        let session = LayoutSession::create(text, &style, &self.font_set.get(bold, italic));
         let mut blobs = Vec::new();
 
         for layout_run in session.iter_all() {
@@ -114,7 +143,7 @@ impl CachingShaper {
                 let mut blob_builder = TextBlobBuilder::new();
                 let count = layout_run.glyphs().count();
                 let (glyphs, positions) =
-                    blob_builder.alloc_run_pos_h(&skia_font, count, ascent, None);
+                    blob_builder.alloc_run_pos_h(&skia_font, count, baseline, None);
 
                 for (i, glyph) in layout_run.glyphs().enumerate() {
                     glyphs[i] = glyph.glyph_id as u16;
@@ -130,8 +159,73 @@ impl CachingShaper {
         blobs
     }
 
+    // Skribo always shapes with harfbuzz's default features and has no way to pass others, so text
+    // with features set is shaped with harfbuzz directly through harfbuzz_rs. Skribo still picks
+    // the font for each part of the text, which keeps font fallback the same.
+    fn shape_with_features(
+        &mut self,
+        text: &str,
+        bold: bool,
+        italic: bool,
+        baseline: f32,
+    ) -> Vec<TextBlob> {
+        let runs: Vec<(Range<usize>, SkriboFont)> = self
+            .font_set
+            .get(bold, italic)
+            .itemize(text)
+            .map(|(range, skribo_font)| (range, skribo_font.clone()))
+            .collect();
+
+        let mut blobs = Vec::new();
+        let mut pen_x = 0.0;
+
+        for (range, skribo_font) in runs {
+            let scale = self.options.size / skribo_font.font.metrics().units_per_em as f32;
+            let buffer = UnicodeBuffer::new().add_str(&text[range]);
+            let output = match get_harfbuzz_font(&mut self.harfbuzz_font_cache, &skribo_font) {
+                Some(harfbuzz_font) => {
+                    harfbuzz_shape(harfbuzz_font, buffer, &self.harfbuzz_features)
+                }
+                None => {
+                    warn!("Could not read font data to shape with features");
+                    continue;
+                }
+            };
+
+            let glyphs: Vec<(u16, f32)> = output
+                .get_glyph_infos()
+                .iter()
+                .zip(output.get_glyph_positions().iter())
+                .map(|(info, position)| {
+                    let x = pen_x + position.x_offset as f32 * scale;
+                    pen_x += position.x_advance as f32 * scale;
+                    (info.codepoint as u16, x)
+                })
+                .collect();
+
+            if let Some(skia_font) = self.get_skia_font(&skribo_font) {
+                let mut blob_builder = TextBlobBuilder::new();
+                let (glyph_ids, positions) =
+                    blob_builder.alloc_run_pos_h(&skia_font, glyphs.len(), baseline, None);
+
+                for (i, (glyph_id, x)) in glyphs.into_iter().enumerate() {
+                    glyph_ids[i] = glyph_id;
+                    positions[i] = x;
+                }
+
+                if let Some(blob) = blob_builder.make() {
+                    blobs.push(blob);
+                }
+            } else {
+                warn!("Could not load skribo font");
+            }
+        }
+
+        blobs
+    }
+
     pub fn shape_cached(&mut self, text: &str, bold: bool, italic: bool) -> &Vec<TextBlob> {
-        let key = ShapeKey::new(text.to_string(), bold, italic);
+        let key = ShapeKey::new(text.to_string(), bold, italic, self.features.clone());
 
         if !self.blob_cache.contains(&key) {
             let blobs = self.shape(text, bold, italic);
@@ -145,13 +239,52 @@ impl CachingShaper {
         let updated = self.options.update(guifont_setting);
         if updated {
             trace!("Font changed: {:?}", self.options);
-            self.font_set = FontSet::new(&self.options.fallback_list, &mut self.font_loader);
+            self.font_set = FontSet::new(&self.options, &mut self.font_loader);
+            self.features = Arc::from(self.options.features.clone());
+            self.harfbuzz_features = self.options.features.iter().map(to_harfbuzz).collect();
+            self.font_cache.clear();
+            self.harfbuzz_font_cache.clear();
+            self.blob_cache.clear();
+        }
+        updated
+    }
+
+    pub fn update_font_variants(&mut self, settings: &FontSettings) -> bool {
+        let updated = self.options.set_variant_families(settings);
+        if updated {
+            trace!("Font variants changed: {:?}", self.options);
+            self.font_set = FontSet::new(&self.options, &mut self.font_loader);
+            self.font_cache.clear();
+            self.harfbuzz_font_cache.clear();
+            self.blob_cache.clear();
+        }
+        updated
+    }
+
+    pub fn update_scale_factor(&mut self, scale_factor: f32) -> bool {
+        let updated = self.options.set_scale_factor(scale_factor);
+        if updated {
+            trace!("Font scale factor changed: {}", scale_factor);
             self.font_cache.clear();
             self.blob_cache.clear();
         }
         updated
     }
 
+    pub fn update_line_space(&mut self, line_space: f32) -> bool {
+        let updated = (self.line_space - line_space).abs() > std::f32::EPSILON;
+        if updated {
+            trace!("Line space changed: {}", line_space);
+            self.line_space = line_space;
+            self.blob_cache.clear();
+        }
+        updated
+    }
+
+    pub fn line_space(&self) -> f32 {
+        self.line_space
+    }
+
     pub fn font_base_dimensions(&mut self) -> (f32, f32) {
         let metrics = self.metrics();
         let font_height =
@@ -186,5 +319,41 @@ impl CachingShaper {
     pub fn underline_position(&mut self) -> f32 {
         let metrics = self.metrics();
         -metrics.underline_position * self.options.size / metrics.units_per_em as f32
+            + self.line_space / 2.0
+    }
+}
+
+fn to_harfbuzz(feature: &FontFeature) -> Feature {
+    let mut tag = [' '; 4];
+    for (index, character) in feature.tag.chars().take(4).enumerate() {
+        tag[index] = character;
+    }
+    Feature::new(Tag::new(tag[0], tag[1], tag[2], tag[3]), feature.value, ..)
+}
+
+// Harfbuzz fonts are kept at the font's own units per em, so they only need to be rebuilt when the
+// font itself changes.
+fn get_harfbuzz_font<'a>(
+    harfbuzz_font_cache: &'a mut LruCache<String, Owned<HarfbuzzFont<'static>>>,
+    skribo_font: &SkriboFont,
+) -> Option<&'a HarfbuzzFont<'static>> {
+    let font_name = skribo_font.font.postscript_name()?;
+    if !harfbuzz_font_cache.contains(&font_name) {
+        let font_data = skribo_font.font.copy_font_data()?;
+        let blob = Blob::with_bytes_owned(font_data, |data| &data[..]);
+        let font = HarfbuzzFont::new(Face::new(blob, face_index(skribo_font)));
+        harfbuzz_font_cache.put(font_name.clone(), font);
+    }
+
+    harfbuzz_font_cache.get(&font_name).map(|font| &**font)
+}
+
+// Fonts in a collection such as a .ttc file share its data, so the face has to be picked by index.
+fn face_index(skribo_font: &SkriboFont) -> u32 {
+    match skribo_font.font.handle() {
+        Some(Handle::Path { font_index, .. }) | Some(Handle::Memory { font_index, .. }) => {
+            font_index
+        }
+        None => 0,
     }
 }
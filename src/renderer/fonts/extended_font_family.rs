@@ -1,4 +1,8 @@
-use font_kit::{family_handle::FamilyHandle, font::Font, properties::Properties};
+use font_kit::{
+    family_handle::FamilyHandle,
+    font::Font,
+    properties::{Properties, Style},
+};
 use skribo::{FontFamily, FontRef as SkriboFont};
 
 #[derive(Clone)]
@@ -21,27 +25,29 @@ impl ExtendedFontFamily {
         self.fonts.push(font);
     }
 
+    /// Picks the font closest to the requested properties. A family without an exact match, such
+    /// as one without a bold italic face, keeps the requested style before the requested weight.
     pub fn get(&self, props: Properties) -> Option<&Font> {
-        if let Some(first_handle) = &self.fonts.first() {
-            for handle in &self.fonts {
-                let font = &handle.font;
-                let properties = font.properties();
-
-                if properties.weight == props.weight
-                    && properties.style == props.style
-                    && properties.stretch == props.stretch
-                {
-                    return Some(&font);
-                }
-            }
-
-            return Some(&first_handle.font);
-        }
-
-        None
+        self.fonts
+            .iter()
+            .map(|handle| &handle.font)
+            .min_by_key(|font| property_distance(font.properties(), props))
     }
 }
 
+// Compared in order of importance: style, then width since a condensed face breaks the cell grid,
+// then weight
+fn property_distance(properties: Properties, requested: Properties) -> (u32, u32, u32) {
+    let style = match (properties.style, requested.style) {
+        (found, requested) if found == requested => 0,
+        (Style::Italic, Style::Oblique) | (Style::Oblique, Style::Italic) => 1,
+        _ => 2,
+    };
+    let stretch = ((properties.stretch.0 - requested.stretch.0).abs() * 1000.0) as u32;
+    let weight = (properties.weight.0 - requested.weight.0).abs() as u32;
+    (style, stretch, weight)
+}
+
 impl From<FamilyHandle> for ExtendedFontFamily {
     fn from(handle: FamilyHandle) -> Self {
         handle
@@ -101,6 +107,25 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_property_distance_prefers_style() {
+        let bold_italic = Properties {
+            weight: Weight::BOLD,
+            style: Style::Italic,
+            stretch: Stretch::NORMAL,
+        };
+        let italic = Properties {
+            style: Style::Italic,
+            ..PROPERTIES
+        };
+        let bold = Properties {
+            weight: Weight::BOLD,
+            ..PROPERTIES
+        };
+        assert_eq!(property_distance(bold_italic, bold_italic), (0, 0, 0));
+        assert!(property_distance(italic, bold_italic) < property_distance(bold, bold_italic));
+    }
+
     #[test]
     fn test_get() {
         let mut eft = ExtendedFontFamily::new();
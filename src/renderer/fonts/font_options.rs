@@ -1,8 +1,45 @@
+use crate::settings::*;
+
+/// Families used for bold, italic and bold italic text instead of the guifont. Each one is a comma
+/// separated fallback list like the one in guifont, and an empty setting keeps the guifont.
+#[setting_prefix = "font"]
+#[derive(Clone, PartialEq, SettingGroup)]
+pub struct FontSettings {
+    pub bold: String,
+    pub italic: String,
+    pub bold_italic: String,
+}
+
+impl Default for FontSettings {
+    fn default() -> Self {
+        Self {
+            bold: String::new(),
+            italic: String::new(),
+            bold_italic: String::new(),
+        }
+    }
+}
+
+/// An OpenType feature such as liga or ss01 and the value it is set to. Most features are switched
+/// on with 1 and off with 0, while character variants pick an alternate with larger values.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FontFeature {
+    pub tag: String,
+    pub value: u32,
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct FontOptions {
     previous_guifont_setting: Option<String>,
     pub fallback_list: Vec<String>,
+    bold_fallback_list: Vec<String>,
+    italic_fallback_list: Vec<String>,
+    bold_italic_fallback_list: Vec<String>,
+    pub features: Vec<FontFeature>,
+    /// The size fonts are drawn at, which is the guifont size zoomed by the scale factor
     pub size: f32,
+    guifont_size: f32,
+    scale_factor: f32,
 }
 
 impl FontOptions {
@@ -10,8 +47,59 @@ impl FontOptions {
         FontOptions {
             previous_guifont_setting: None,
             fallback_list: vec![name],
+            bold_fallback_list: Vec::new(),
+            italic_fallback_list: Vec::new(),
+            bold_italic_fallback_list: Vec::new(),
+            features: Vec::new(),
             size,
+            guifont_size: size,
+            scale_factor: 1.0,
+        }
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f32) -> bool {
+        if (self.scale_factor - scale_factor).abs() < std::f32::EPSILON {
+            return false;
         }
+        self.scale_factor = scale_factor;
+        self.size = self.guifont_size * scale_factor;
+        true
+    }
+
+    pub fn set_variant_families(&mut self, settings: &FontSettings) -> bool {
+        let bold = parse_fallback_list(&settings.bold);
+        let italic = parse_fallback_list(&settings.italic);
+        let bold_italic = parse_fallback_list(&settings.bold_italic);
+        if self.bold_fallback_list == bold
+            && self.italic_fallback_list == italic
+            && self.bold_italic_fallback_list == bold_italic
+        {
+            return false;
+        }
+        self.bold_fallback_list = bold;
+        self.italic_fallback_list = italic;
+        self.bold_italic_fallback_list = bold_italic;
+        true
+    }
+
+    /// The families to pick a font with the given weight and style from, followed by the guifont
+    /// for any glyphs they lack. Bold italic text uses the italic families when it has none of its
+    /// own, since fonts differ far more in their italic cuts than in their weights.
+    pub fn variant_fallback_list(&self, bold: bool, italic: bool) -> Vec<String> {
+        let variants = match (bold, italic) {
+            (false, false) => vec![],
+            (true, false) => vec![&self.bold_fallback_list],
+            (false, true) => vec![&self.italic_fallback_list],
+            (true, true) => vec![&self.bold_italic_fallback_list, &self.italic_fallback_list],
+        };
+        variants
+            .into_iter()
+            .find(|fallback_list| !fallback_list.is_empty())
+            .into_iter()
+            .flatten()
+            .chain(self.fallback_list.iter())
+            .cloned()
+            .collect()
     }
 
     pub fn update(self: &mut FontOptions, guifont_setting: &str) -> bool {
@@ -26,11 +114,7 @@ impl FontOptions {
         let mut updated = false;
 
         if let Some(parts) = parts.next() {
-            let parsed_fallback_list: Vec<String> = parts
-                .split(',')
-                .filter(|fallback| !fallback.is_empty())
-                .map(|fallback| fallback.to_string())
-                .collect();
+            let parsed_fallback_list = parse_fallback_list(parts);
 
             if !parsed_fallback_list.is_empty() && self.fallback_list != parsed_fallback_list {
                 self.fallback_list = parsed_fallback_list;
@@ -38,17 +122,150 @@ impl FontOptions {
             }
         }
 
+        // Features are part of the font's description, so a guifont without any turns them all
+        // back to the font's defaults
+        let mut features = Vec::new();
+
         for part in parts {
-            if part.starts_with('h') && part.len() > 1 {
+            if part.starts_with('+') || part.starts_with('-') {
+                features.extend(parse_features(part));
+            } else if part.starts_with('h') && part.len() > 1 {
                 if let Ok(size) = part[1..].parse::<f32>() {
-                    if (self.size - size).abs() > std::f32::EPSILON {
-                        self.size = size;
+                    if (self.guifont_size - size).abs() > std::f32::EPSILON {
+                        self.guifont_size = size;
+                        self.size = size * self.scale_factor;
                         updated = true;
                     }
                 }
             }
         }
 
+        if self.features != features {
+            self.features = features;
+            updated = true;
+        }
+
         updated
     }
 }
+
+// Parses a comma separated list of features such as -liga,+ss01,+cv02=2. A plus turns the feature
+// on, or sets it to the value after the equals sign, and a minus turns it off.
+fn parse_features(features: &str) -> Vec<FontFeature> {
+    features
+        .split(',')
+        .filter(|feature| !feature.is_empty())
+        .filter_map(|feature| {
+            let (enabled, feature) = if let Some(feature) = feature.strip_prefix('+') {
+                (true, feature)
+            } else if let Some(feature) = feature.strip_prefix('-') {
+                (false, feature)
+            } else {
+                log::warn!("Font feature {} should start with + or -", feature);
+                return None;
+            };
+
+            let mut parts = feature.splitn(2, '=');
+            let tag = parts.next().unwrap_or_default();
+            let value = match (enabled, parts.next()) {
+                (false, _) => Some(0),
+                (true, None) => Some(1),
+                (true, Some(value)) => value.parse::<u32>().ok(),
+            };
+
+            let valid_tag =
+                !tag.is_empty() && tag.len() <= 4 && tag.chars().all(|c| c.is_ascii_alphanumeric());
+            match value {
+                Some(value) if valid_tag => Some(FontFeature {
+                    tag: tag.to_string(),
+                    value,
+                }),
+                _ => {
+                    log::warn!("Could not parse font feature {}", feature);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn parse_fallback_list(fallback_list: &str) -> Vec<String> {
+    fallback_list
+        .split(',')
+        .filter(|fallback| !fallback.is_empty())
+        .map(|fallback| fallback.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_factor_zooms_guifont_size() {
+        let mut options = FontOptions::new(String::from("Fira Code"), 14.0);
+        assert!(options.set_scale_factor(2.0));
+        assert!(!options.set_scale_factor(2.0));
+        assert!((options.size - 28.0).abs() < std::f32::EPSILON);
+
+        assert!(options.update("Fira Code:h10"));
+        assert!((options.size - 20.0).abs() < std::f32::EPSILON);
+
+        assert!(options.set_scale_factor(1.0));
+        assert!((options.size - 10.0).abs() < std::f32::EPSILON);
+    }
+
+    #[test]
+    fn test_variant_fallback_list() {
+        let mut options = FontOptions::new(String::from("Fira Code"), 14.0);
+        let settings = FontSettings {
+            bold: String::new(),
+            italic: String::from("Victor Mono,Operator Mono"),
+            bold_italic: String::new(),
+        };
+        assert!(options.set_variant_families(&settings));
+        assert!(!options.set_variant_families(&settings));
+
+        let italic = vec![
+            String::from("Victor Mono"),
+            String::from("Operator Mono"),
+            String::from("Fira Code"),
+        ];
+        assert_eq!(
+            options.variant_fallback_list(false, false),
+            options.fallback_list
+        );
+        assert_eq!(
+            options.variant_fallback_list(true, false),
+            options.fallback_list
+        );
+        assert_eq!(options.variant_fallback_list(false, true), italic);
+        assert_eq!(options.variant_fallback_list(true, true), italic);
+    }
+
+    #[test]
+    fn test_features_in_guifont() {
+        let mut options = FontOptions::new(String::from("Fira Code"), 14.0);
+        assert!(options.update("Fira Code:h14:-liga,+ss01,+cv02=2,+bad=x"));
+        assert_eq!(
+            options.features,
+            vec![
+                FontFeature {
+                    tag: String::from("liga"),
+                    value: 0
+                },
+                FontFeature {
+                    tag: String::from("ss01"),
+                    value: 1
+                },
+                FontFeature {
+                    tag: String::from("cv02"),
+                    value: 2
+                },
+            ]
+        );
+
+        assert!(options.update("Fira Code:h14"));
+        assert!(options.features.is_empty());
+    }
+}
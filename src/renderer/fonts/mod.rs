@@ -3,3 +3,5 @@ mod extended_font_family;
 mod font_loader;
 mod font_options;
 mod utils;
+
+pub use font_options::FontSettings;
@@ -0,0 +1,462 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use skulpin::skia_safe::{Canvas, Color, Paint, Rect};
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::CachingShaper;
+use crate::bridge::MessageKind;
+use crate::editor::{Style, StyledText};
+use crate::redraw_scheduler::REDRAW_SCHEDULER;
+use crate::settings::*;
+
+const PANE_MARGIN: f32 = 8.0;
+const ACCENT_WIDTH: f32 = 3.0;
+const MAX_PANE_WIDTH_RATIO: f32 = 0.6;
+
+#[setting_prefix = "message"]
+#[derive(Clone, SettingGroup)]
+pub struct MessageSettings {
+    timeout: f32,
+    max_count: u64,
+}
+
+impl Default for MessageSettings {
+    fn default() -> Self {
+        Self {
+            timeout: 5.0,
+            max_count: 5,
+        }
+    }
+}
+
+pub(super) type StyledLine = Vec<(String, Option<Arc<Style>>)>;
+
+struct Message {
+    kind: MessageKind,
+    content: StyledText,
+    shown_at: Instant,
+}
+
+fn is_error(kind: &MessageKind) -> bool {
+    matches!(
+        kind,
+        MessageKind::Error | MessageKind::EchoError | MessageKind::LuaError | MessageKind::RpcError
+    )
+}
+
+fn accent_color(kind: &MessageKind) -> Option<Color> {
+    match kind {
+        kind if is_error(kind) => Some(Color::from_rgb(224, 108, 117)),
+        MessageKind::Warning => Some(Color::from_rgb(229, 192, 123)),
+        MessageKind::SearchCount => Some(Color::from_rgb(97, 175, 239)),
+        MessageKind::Confirm | MessageKind::ConfirmSubstitute | MessageKind::ReturnPrompt => {
+            Some(Color::from_rgb(152, 195, 121))
+        }
+        _ => None,
+    }
+}
+
+// Messages which require a response from the user stay on screen until neovim clears them.
+fn is_persistent(kind: &MessageKind) -> bool {
+    matches!(
+        kind,
+        MessageKind::Confirm | MessageKind::ConfirmSubstitute | MessageKind::ReturnPrompt
+    )
+}
+
+pub(super) fn text_columns(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+// Splits styled text into lines on newlines and wraps any line wider than max_columns.
+pub(super) fn wrap_styled_text(
+    content: &[(String, Option<Arc<Style>>)],
+    max_columns: usize,
+) -> Vec<StyledLine> {
+    let max_columns = max_columns.max(1);
+    let mut lines = vec![Vec::new()];
+    let mut current_columns = 0;
+
+    for (text, style) in content {
+        for (index, segment) in text.split('\n').enumerate() {
+            if index > 0 {
+                lines.push(Vec::new());
+                current_columns = 0;
+            }
+
+            let mut current_text = String::new();
+            for grapheme in segment.graphemes(true) {
+                if current_columns >= max_columns {
+                    if !current_text.is_empty() {
+                        lines
+                            .last_mut()
+                            .unwrap()
+                            .push((std::mem::take(&mut current_text), style.clone()));
+                    }
+                    lines.push(Vec::new());
+                    current_columns = 0;
+                }
+
+                current_text.push_str(grapheme);
+                current_columns += 1;
+            }
+
+            if !current_text.is_empty() {
+                lines
+                    .last_mut()
+                    .unwrap()
+                    .push((current_text, style.clone()));
+            }
+        }
+    }
+
+    lines
+}
+
+pub(super) fn line_columns(line: &[(String, Option<Arc<Style>>)]) -> usize {
+    line.iter().map(|(text, _)| text_columns(text)).sum()
+}
+
+pub struct MessageRenderer {
+    messages: Vec<Message>,
+    history: Option<Vec<(MessageKind, StyledText)>>,
+    history_scroll: usize,
+    mode: StyledText,
+    command: StyledText,
+    ruler: StyledText,
+    font_size: (f32, f32),
+}
+
+impl MessageRenderer {
+    pub fn new() -> MessageRenderer {
+        MessageRenderer {
+            messages: Vec::new(),
+            history: None,
+            history_scroll: 0,
+            mode: Vec::new(),
+            command: Vec::new(),
+            ruler: Vec::new(),
+            font_size: (1.0, 1.0),
+        }
+    }
+
+    pub fn show_message(&mut self, kind: MessageKind, content: StyledText, replace_last: bool) {
+        let message = Message {
+            kind,
+            content,
+            shown_at: Instant::now(),
+        };
+
+        // Search counts are only interesting for the latest search, so they replace each other
+        // instead of stacking up.
+        if message.kind == MessageKind::SearchCount {
+            self.messages
+                .retain(|existing| existing.kind != MessageKind::SearchCount);
+        }
+
+        if replace_last && !self.messages.is_empty() {
+            self.messages.pop();
+        }
+
+        self.messages.push(message);
+
+        let max_count = SETTINGS.get::<MessageSettings>().max_count as usize;
+        if self.messages.len() > max_count {
+            let excess = self.messages.len() - max_count;
+            self.messages.drain(0..excess);
+        }
+    }
+
+    pub fn clear_messages(&mut self) {
+        self.messages.clear();
+        self.history = None;
+    }
+
+    pub fn show_history(&mut self, entries: Vec<(MessageKind, StyledText)>) {
+        self.history = Some(entries);
+        self.history_scroll = 0;
+    }
+
+    pub fn history_visible(&self) -> bool {
+        self.history.is_some()
+    }
+
+    pub fn hide_history(&mut self) {
+        self.history = None;
+    }
+
+    pub fn scroll_history(&mut self, rows: i64) {
+        if rows > 0 {
+            self.history_scroll += rows as usize;
+        } else {
+            self.history_scroll = self.history_scroll.saturating_sub(-rows as usize);
+        }
+    }
+
+    pub fn set_mode(&mut self, content: StyledText) {
+        self.mode = content;
+    }
+
+    pub fn set_command(&mut self, content: StyledText) {
+        self.command = content;
+    }
+
+    pub fn set_ruler(&mut self, content: StyledText) {
+        self.ruler = content;
+    }
+
+    fn remove_expired(&mut self) {
+        let timeout = Duration::from_secs_f32(SETTINGS.get::<MessageSettings>().timeout.max(0.0));
+        let waiting_for_user = self
+            .messages
+            .iter()
+            .any(|message| is_persistent(&message.kind));
+
+        if waiting_for_user {
+            return;
+        }
+
+        self.messages
+            .retain(|message| message.shown_at.elapsed() < timeout);
+
+        if let Some(oldest) = self.messages.iter().map(|message| message.shown_at).min() {
+            REDRAW_SCHEDULER.schedule(oldest + timeout);
+        }
+    }
+
+    fn draw_line(
+        &self,
+        canvas: &mut Canvas,
+        shaper: &mut CachingShaper,
+        default_style: &Arc<Style>,
+        line: &[(String, Option<Arc<Style>>)],
+        x: f32,
+        y: f32,
+    ) {
+        let (font_width, font_height) = self.font_size;
+        let mut paint = Paint::default();
+        paint.set_anti_alias(false);
+
+        let mut x = x;
+        for (text, style) in line {
+            let width = text_columns(text) as f32 * font_width;
+            let style = style.as_ref().unwrap_or(default_style);
+
+            if style.colors.background.is_some() || style.reverse {
+                paint.set_color(style.background(&default_style.colors).to_color());
+                canvas.draw_rect(Rect::new(x, y, x + width, y + font_height), &paint);
+            }
+
+            paint.set_color(style.foreground(&default_style.colors).to_color());
+            let trimmed = text.trim_end();
+            if !trimmed.is_empty() {
+                for blob in shaper
+                    .shape_cached(trimmed, style.bold, style.italic)
+                    .iter()
+                {
+                    canvas.draw_text_blob(blob, (x, y), &paint);
+                }
+            }
+
+            x += width;
+        }
+    }
+
+    fn draw_pane(
+        &self,
+        canvas: &mut Canvas,
+        shaper: &mut CachingShaper,
+        default_style: &Arc<Style>,
+        lines: &[StyledLine],
+        accent: Option<Color>,
+        region: Rect,
+    ) {
+        let (font_width, font_height) = self.font_size;
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+
+        let background = default_style.colors.background.clone().unwrap().to_color();
+        paint.set_color(background);
+        canvas.draw_round_rect(region, 4.0, 4.0, &paint);
+
+        let foreground = default_style.colors.foreground.clone().unwrap().to_color();
+        paint.set_color(accent.unwrap_or_else(|| foreground.with_a(80)));
+        canvas.draw_rect(
+            Rect::new(
+                region.left,
+                region.top,
+                region.left + ACCENT_WIDTH,
+                region.bottom,
+            ),
+            &paint,
+        );
+
+        for (index, line) in lines.iter().enumerate() {
+            self.draw_line(
+                canvas,
+                shaper,
+                default_style,
+                line,
+                region.left + font_width,
+                region.top + font_height / 2.0 + index as f32 * font_height,
+            );
+        }
+    }
+
+    fn draw_messages(
+        &self,
+        canvas: &mut Canvas,
+        shaper: &mut CachingShaper,
+        default_style: &Arc<Style>,
+        bounds: Rect,
+    ) {
+        let (font_width, font_height) = self.font_size;
+        let max_columns =
+            ((bounds.width() * MAX_PANE_WIDTH_RATIO / font_width) as usize).saturating_sub(2);
+
+        // The status row at the bottom of the screen is reserved for the mode and ruler
+        let mut bottom = bounds.bottom - font_height - PANE_MARGIN;
+
+        for message in self.messages.iter().rev() {
+            let lines = wrap_styled_text(&message.content, max_columns);
+            let columns = lines
+                .iter()
+                .map(|line| line_columns(line))
+                .max()
+                .unwrap_or(0);
+
+            let width = (columns + 2) as f32 * font_width;
+            let height = (lines.len() + 1) as f32 * font_height;
+            let region = Rect::new(
+                bounds.right - PANE_MARGIN - width,
+                bottom - height,
+                bounds.right - PANE_MARGIN,
+                bottom,
+            );
+
+            if region.top < bounds.top {
+                break;
+            }
+
+            self.draw_pane(
+                canvas,
+                shaper,
+                default_style,
+                &lines,
+                accent_color(&message.kind),
+                region,
+            );
+
+            bottom = region.top - PANE_MARGIN;
+        }
+    }
+
+    fn draw_history(
+        &self,
+        canvas: &mut Canvas,
+        shaper: &mut CachingShaper,
+        default_style: &Arc<Style>,
+        bounds: Rect,
+    ) -> Option<usize> {
+        let entries = self.history.as_ref()?;
+        let (font_width, font_height) = self.font_size;
+
+        let region = Rect::new(
+            bounds.left + bounds.width() * 0.1,
+            bounds.top + bounds.height() * 0.1,
+            bounds.right - bounds.width() * 0.1,
+            bounds.bottom - bounds.height() * 0.1,
+        );
+        let columns = ((region.width() / font_width) as usize).saturating_sub(2);
+        let visible_rows = ((region.height() / font_height) as usize).saturating_sub(1);
+
+        let lines: Vec<StyledLine> = entries
+            .iter()
+            .flat_map(|(_, content)| wrap_styled_text(content, columns))
+            .collect();
+
+        // Scrolling is measured from the bottom of the history so that the latest messages are
+        // visible when the view opens.
+        let max_scroll = lines.len().saturating_sub(visible_rows);
+        let scroll = self.history_scroll.min(max_scroll);
+        let end = lines.len() - scroll;
+        let start = end.saturating_sub(visible_rows);
+
+        self.draw_pane(
+            canvas,
+            shaper,
+            default_style,
+            &lines[start..end],
+            None,
+            region,
+        );
+
+        Some(max_scroll)
+    }
+
+    fn draw_status(
+        &self,
+        canvas: &mut Canvas,
+        shaper: &mut CachingShaper,
+        default_style: &Arc<Style>,
+        bounds: Rect,
+    ) {
+        let (font_width, font_height) = self.font_size;
+        let y = bounds.bottom - font_height;
+
+        self.draw_line(canvas, shaper, default_style, &self.mode, bounds.left, y);
+
+        let mut right = bounds.right;
+        for content in &[&self.ruler, &self.command] {
+            let width = line_columns(content) as f32 * font_width;
+            right -= width + font_width;
+            self.draw_line(canvas, shaper, default_style, content, right, y);
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        canvas: &mut Canvas,
+        shaper: &mut CachingShaper,
+        default_style: &Arc<Style>,
+        bounds: Rect,
+        font_size: (f32, f32),
+    ) {
+        self.font_size = font_size;
+        self.remove_expired();
+
+        self.draw_status(canvas, shaper, default_style, bounds);
+        self.draw_messages(canvas, shaper, default_style, bounds);
+
+        if let Some(max_scroll) = self.draw_history(canvas, shaper, default_style, bounds) {
+            self.history_scroll = self.history_scroll.min(max_scroll);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unstyled(text: &str) -> (String, Option<Arc<Style>>) {
+        (text.to_string(), None)
+    }
+
+    #[test]
+    fn test_wrap_splits_newlines() {
+        let lines = wrap_styled_text(&[unstyled("foo\nbar")], 80);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0][0].0, "foo");
+        assert_eq!(lines[1][0].0, "bar");
+    }
+
+    #[test]
+    fn test_wrap_long_lines() {
+        let lines = wrap_styled_text(&[unstyled("abc"), unstyled("defgh")], 3);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(line_columns(&lines[0]), 3);
+        assert_eq!(lines[1][0].0, "def");
+        assert_eq!(lines[2][0].0, "gh");
+    }
+}
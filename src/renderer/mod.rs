@@ -7,17 +7,31 @@ use skulpin::skia_safe::{colors, dash_path_effect, BlendMode, Canvas, Color, Pai
 use skulpin::CoordinateSystemHelper;
 
 pub mod animation_utils;
+mod box_drawing;
 pub mod cursor_renderer;
 mod fonts;
+mod message_renderer;
+mod popup_menu_renderer;
+mod preedit_renderer;
 mod rendered_window;
+mod tab_line_renderer;
 
 pub use fonts::caching_shaper::CachingShaper;
-pub use rendered_window::{RenderedWindow, WindowDrawDetails};
+pub use fonts::FontSettings;
+pub use message_renderer::MessageSettings;
+pub use popup_menu_renderer::PopupMenuSettings;
+pub use rendered_window::{ExternalWindowDetails, RenderedWindow, WindowDrawDetails};
 
 use crate::bridge::EditorMode;
 use crate::editor::{Colors, DrawCommand, Style, WindowDrawCommand};
 use crate::settings::*;
+use crate::window::WindowSettings;
+use box_drawing::{draw_procedural_glyph, is_procedural_glyph};
 use cursor_renderer::CursorRenderer;
+use message_renderer::MessageRenderer;
+use popup_menu_renderer::PopupMenuRenderer;
+use preedit_renderer::PreeditRenderer;
+use tab_line_renderer::TabLineRenderer;
 
 #[setting_prefix = "window"]
 #[derive(Clone, SettingGroup)]
@@ -39,9 +53,37 @@ impl Default for RendererSettings {
     }
 }
 
+/// Box drawing, block and powerline glyphs are drawn by the renderer to fill their cells unless
+/// box_drawing is turned off, in which case they come from the font like any other text.
+#[derive(Clone, SettingGroup)]
+pub struct BoxDrawingSettings {
+    pub box_drawing: bool,
+}
+
+impl Default for BoxDrawingSettings {
+    fn default() -> Self {
+        Self { box_drawing: true }
+    }
+}
+
+fn offset_region(region: Rect, vertical_offset: f32) -> Rect {
+    Rect::new(
+        region.left,
+        region.top + vertical_offset,
+        region.right,
+        region.bottom + vertical_offset,
+    )
+}
+
 pub struct Renderer {
     rendered_windows: HashMap<u64, RenderedWindow>,
     cursor_renderer: CursorRenderer,
+    message_renderer: MessageRenderer,
+    popup_menu_renderer: PopupMenuRenderer,
+    preedit_renderer: PreeditRenderer,
+    tab_line_renderer: TabLineRenderer,
+    reconnecting: bool,
+    box_drawing: bool,
 
     pub current_mode: EditorMode,
     pub paint: Paint,
@@ -57,6 +99,10 @@ impl Renderer {
     pub fn new(batched_draw_command_receiver: Receiver<Vec<DrawCommand>>) -> Renderer {
         let rendered_windows = HashMap::new();
         let cursor_renderer = CursorRenderer::new();
+        let message_renderer = MessageRenderer::new();
+        let popup_menu_renderer = PopupMenuRenderer::new();
+        let preedit_renderer = PreeditRenderer::new();
+        let tab_line_renderer = TabLineRenderer::new();
 
         let current_mode = EditorMode::Unknown(String::from(""));
         let mut paint = Paint::new(colors::WHITE, None);
@@ -75,6 +121,12 @@ impl Renderer {
         Renderer {
             rendered_windows,
             cursor_renderer,
+            message_renderer,
+            popup_menu_renderer,
+            preedit_renderer,
+            tab_line_renderer,
+            reconnecting: false,
+            box_drawing: BoxDrawingSettings::default().box_drawing,
             current_mode,
             paint,
             shaper,
@@ -88,9 +140,189 @@ impl Renderer {
 
     fn update_font(&mut self, guifont_setting: &str) {
         if self.shaper.update_font(guifont_setting) {
-            let (font_width, font_height) = self.shaper.font_base_dimensions();
-            self.font_width = font_width;
-            self.font_height = font_height.ceil();
+            self.update_font_dimensions();
+        }
+    }
+
+    fn update_line_space(&mut self, line_space: u64) {
+        if self.shaper.update_line_space(line_space as f32) {
+            self.update_font_dimensions();
+        }
+    }
+
+    fn update_scale_factor(&mut self, scale_factor: f32) -> bool {
+        let updated = scale_factor > 0.0 && self.shaper.update_scale_factor(scale_factor);
+        if updated {
+            self.update_font_dimensions();
+        }
+        updated
+    }
+
+    fn update_font_variants(&mut self, font_variants: &FontSettings) {
+        if self.shaper.update_font_variants(font_variants) {
+            self.update_font_dimensions();
+        }
+    }
+
+    fn update_font_dimensions(&mut self) {
+        let (font_width, font_height) = self.shaper.font_base_dimensions();
+        self.font_width = font_width;
+        self.font_height = font_height.ceil() + self.shaper.line_space();
+    }
+
+    fn rebuild_window_surfaces(&mut self, scaling: f32) {
+        let mut rendered_windows = std::mem::take(&mut self.rendered_windows);
+        for window in rendered_windows.values_mut() {
+            window.rebuild_surfaces(self, scaling);
+        }
+        self.rendered_windows = rendered_windows;
+    }
+
+    pub fn message_history_visible(&self) -> bool {
+        self.message_renderer.history_visible()
+    }
+
+    pub fn scroll_message_history(&mut self, rows: i64) {
+        self.message_renderer.scroll_history(rows);
+    }
+
+    pub fn hide_message_history(&mut self) {
+        self.message_renderer.hide_history();
+    }
+
+    pub fn tab_bar_height(&self) -> f32 {
+        self.tab_line_renderer.height(self.font_height)
+    }
+
+    pub fn tab_at(&self, x: f32, y: f32) -> Option<u64> {
+        self.tab_line_renderer.tab_at(x, y)
+    }
+
+    // Window regions are stored in draw order, so the last match is the one on top.
+    pub fn window_region_at(&self, x: u32, y: u32) -> Option<&WindowDrawDetails> {
+        self.window_regions.iter().rev().find(|details| {
+            x >= details.region.left as u32
+                && x < details.region.right as u32
+                && y >= details.region.top as u32
+                && y < details.region.bottom as u32
+        })
+    }
+
+    pub fn set_preedit(&mut self, text: String, cursor: Option<usize>) {
+        self.preedit_renderer.set_preedit(text, cursor);
+    }
+
+    pub fn clear_preedit(&mut self) {
+        self.preedit_renderer.clear();
+    }
+
+    // The cell under the cursor in logical window coordinates, used to place the input method's
+    // candidate window next to the text being composed.
+    pub fn cursor_region(&self) -> Option<Rect> {
+        if self.cursor_in_external_window() {
+            return None;
+        }
+
+        let destination = self.cursor_renderer.destination();
+        let top = destination.y + self.tab_bar_height();
+        Some(Rect::new(
+            destination.x,
+            top,
+            destination.x + self.font_width,
+            top + self.font_height,
+        ))
+    }
+
+    fn cursor_in_external_window(&self) -> bool {
+        self.rendered_windows
+            .get(&self.cursor_renderer.grid_id())
+            .map(|window| window.external.is_some())
+            .unwrap_or(false)
+    }
+
+    // Grids neovim has moved out of the main window with win_external_pos, sized in logical
+    // pixels.
+    pub fn external_windows(&self) -> Vec<ExternalWindowDetails> {
+        let font_width = self.font_width;
+        let font_height = self.font_height;
+        self.rendered_windows
+            .values()
+            .filter(|window| !window.hidden)
+            .filter_map(|window| {
+                let window_handle = window.external?;
+                let region = window.pixel_region(font_width, font_height);
+                Some(ExternalWindowDetails {
+                    grid_id: window.id,
+                    window_handle,
+                    width: region.width(),
+                    height: region.height(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn draw_external_window(
+        &mut self,
+        grid_id: u64,
+        root_canvas: &mut Canvas,
+        coordinate_system_helper: &CoordinateSystemHelper,
+        dt: f32,
+    ) {
+        let default_background = self.get_default_background();
+        let settings = SETTINGS.get::<RendererSettings>();
+
+        root_canvas.save();
+        coordinate_system_helper.use_logical_coordinates(root_canvas);
+
+        if let Some(window) = self.rendered_windows.get_mut(&grid_id) {
+            window.draw_external(
+                root_canvas,
+                &settings,
+                default_background,
+                self.font_width,
+                self.font_height,
+                dt,
+            );
+        }
+
+        if self.cursor_renderer.grid_id() == grid_id {
+            self.cursor_renderer.draw(
+                &self.default_style.colors,
+                (self.font_width, self.font_height),
+                &self.current_mode,
+                &mut self.shaper,
+                root_canvas,
+                dt,
+            );
+        }
+
+        root_canvas.restore();
+    }
+
+    // Dims the last frame received from neovim and explains why nothing responds to input.
+    fn draw_reconnecting_overlay(&mut self, canvas: &mut Canvas, bounds: Rect) {
+        let background = self.get_default_background();
+        let foreground = self
+            .default_style
+            .colors
+            .foreground
+            .clone()
+            .unwrap()
+            .to_color();
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(false);
+        paint.set_color(background.with_a(200));
+        canvas.draw_rect(bounds, &paint);
+
+        let text = "Reconnecting\u{2026}";
+        let text_width = text.chars().count() as f32 * self.font_width;
+        let left = bounds.left + (bounds.width() - text_width) / 2.0;
+        let top = bounds.top + (bounds.height() - self.font_height) / 2.0;
+
+        paint.set_color(foreground);
+        for blob in self.shaper.shape_cached(text, false, false).iter() {
+            canvas.draw_text_blob(blob, (left, top), &paint);
         }
     }
 
@@ -124,8 +356,9 @@ impl Renderer {
         let region = self.compute_text_region(grid_pos, cell_width);
         let style = style.as_ref().unwrap_or(&self.default_style);
 
+        let background = style.background(&self.default_style.colors).to_color();
         self.paint
-            .set_color(style.background(&self.default_style.colors).to_color());
+            .set_color(background.with_a(style.background_alpha()));
         canvas.draw_rect(region, &self.paint);
     }
 
@@ -178,17 +411,16 @@ impl Renderer {
             );
         }
 
-        self.paint
-            .set_color(style.foreground(&self.default_style.colors).to_color());
+        let foreground = style.foreground(&self.default_style.colors).to_color();
+        self.paint.set_color(foreground);
+        // Wide characters take up more cells than characters in the text, so procedural glyphs are
+        // only placed when every character has a cell of its own
+        let one_cell_per_character = text.chars().count() == cell_width as usize;
         let text = text.trim_end();
-        if !text.is_empty() {
-            for blob in self
-                .shaper
-                .shape_cached(text, style.bold, style.italic)
-                .iter()
-            {
-                canvas.draw_text_blob(blob, (x, y), &self.paint);
-            }
+        if self.box_drawing && one_cell_per_character && text.chars().any(is_procedural_glyph) {
+            self.draw_text_with_procedural_glyphs(canvas, text, (x, y), style, foreground);
+        } else if !text.is_empty() {
+            self.draw_text(canvas, text, (x, y), style);
         }
 
         if style.strikethrough {
@@ -201,6 +433,53 @@ impl Renderer {
         canvas.restore();
     }
 
+    fn draw_text(&mut self, canvas: &mut Canvas, text: &str, position: (f32, f32), style: &Style) {
+        for blob in self
+            .shaper
+            .shape_cached(text, style.bold, style.italic)
+            .iter()
+        {
+            canvas.draw_text_blob(blob, position, &self.paint);
+        }
+    }
+
+    // Box drawing, block and powerline glyphs are drawn to fill their cell exactly, and the text
+    // between them is shaped as usual.
+    fn draw_text_with_procedural_glyphs(
+        &mut self,
+        canvas: &mut Canvas,
+        text: &str,
+        (x, y): (f32, f32),
+        style: &Style,
+        foreground: Color,
+    ) {
+        let mut run_start = None;
+        for (column, (index, character)) in text.char_indices().enumerate() {
+            if !is_procedural_glyph(character) {
+                run_start.get_or_insert((index, column));
+                continue;
+            }
+
+            if let Some((start_index, start_column)) = run_start.take() {
+                let run_x = x + start_column as f32 * self.font_width;
+                self.draw_text(canvas, &text[start_index..index], (run_x, y), style);
+            }
+
+            let cell = Rect::from_xywh(
+                x + column as f32 * self.font_width,
+                y,
+                self.font_width,
+                self.font_height,
+            );
+            draw_procedural_glyph(canvas, character, cell, foreground);
+        }
+
+        if let Some((start_index, start_column)) = run_start {
+            let run_x = x + start_column as f32 * self.font_width;
+            self.draw_text(canvas, &text[start_index..], (run_x, y), style);
+        }
+    }
+
     pub fn handle_draw_command(
         &mut self,
         root_canvas: &mut Canvas,
@@ -225,11 +504,12 @@ impl Renderer {
                     grid_top,
                     width,
                     height,
+                    window_handle,
                     ..
                 } = command
                 {
                     warn!("Created window {}", grid_id);
-                    let new_window = RenderedWindow::new(
+                    let mut new_window = RenderedWindow::new(
                         root_canvas,
                         &self,
                         grid_id,
@@ -238,6 +518,7 @@ impl Renderer {
                         height,
                         scaling,
                     );
+                    new_window.window_handle = window_handle;
                     self.rendered_windows.insert(grid_id, new_window);
                 } else {
                     error!("WindowDrawCommand sent for uninitialized grid {}", grid_id);
@@ -249,12 +530,69 @@ impl Renderer {
             DrawCommand::FontChanged(new_font) => {
                 self.update_font(&new_font);
             }
+            DrawCommand::LineSpaceChanged(new_line_space) => {
+                self.update_line_space(new_line_space);
+            }
+            DrawCommand::FontVariantsChanged(new_font_variants) => {
+                self.update_font_variants(&new_font_variants);
+            }
             DrawCommand::DefaultStyleChanged(new_style) => {
                 self.default_style = Arc::new(new_style);
             }
             DrawCommand::ModeChanged(new_mode) => {
                 self.current_mode = new_mode;
             }
+            DrawCommand::MessageShow {
+                kind,
+                content,
+                replace_last,
+            } => {
+                self.message_renderer
+                    .show_message(kind, content, replace_last);
+            }
+            DrawCommand::MessageClear => {
+                self.message_renderer.clear_messages();
+            }
+            DrawCommand::MessageShowMode(content) => {
+                self.message_renderer.set_mode(content);
+            }
+            DrawCommand::MessageShowCommand(content) => {
+                self.message_renderer.set_command(content);
+            }
+            DrawCommand::MessageRuler(content) => {
+                self.message_renderer.set_ruler(content);
+            }
+            DrawCommand::MessageHistoryShow(entries) => {
+                self.message_renderer.show_history(entries);
+            }
+            DrawCommand::PopupMenuShow {
+                items,
+                selected,
+                anchor_left,
+                anchor_top,
+                styles,
+            } => {
+                self.popup_menu_renderer
+                    .show(items, selected, (anchor_left, anchor_top), styles);
+            }
+            DrawCommand::PopupMenuSelect(selected) => {
+                self.popup_menu_renderer.select(selected);
+            }
+            DrawCommand::PopupMenuHide => {
+                self.popup_menu_renderer.hide();
+            }
+            DrawCommand::TablineUpdate { current_tab, tabs } => {
+                self.tab_line_renderer.update(current_tab, tabs);
+            }
+            DrawCommand::ShowTabLine(show_tab_line) => {
+                self.tab_line_renderer.set_show_tab_line(show_tab_line);
+            }
+            DrawCommand::PopupMenuBlend(blend) => {
+                self.popup_menu_renderer.set_blend(blend);
+            }
+            DrawCommand::Reconnecting(reconnecting) => {
+                self.reconnecting = reconnecting;
+            }
             _ => {}
         }
     }
@@ -267,7 +605,9 @@ impl Renderer {
         scaling: f32,
     ) -> bool {
         trace!("Rendering");
+        self.box_drawing = SETTINGS.get::<BoxDrawingSettings>().box_drawing;
         let mut font_changed = false;
+        let previous_tab_bar_height = self.tab_bar_height();
 
         let draw_commands: Vec<DrawCommand> = self
             .batched_draw_command_receiver
@@ -276,12 +616,29 @@ impl Renderer {
             .flatten() // Iterator of DrawCommand
             .collect(); // Vec of DrawCommand
         for draw_command in draw_commands.into_iter() {
-            if let DrawCommand::FontChanged(_) = draw_command {
+            if let DrawCommand::FontChanged(_)
+            | DrawCommand::LineSpaceChanged(_)
+            | DrawCommand::FontVariantsChanged(_) = draw_command
+            {
                 font_changed = true;
             }
             self.handle_draw_command(root_canvas, draw_command, scaling);
         }
 
+        // Zooming and g:neovide_scale_factor both only change the setting, which is applied here
+        let scale_factor = SETTINGS.get::<WindowSettings>().scale_factor;
+        if self.update_scale_factor(scale_factor) {
+            font_changed = true;
+        }
+
+        if font_changed {
+            self.rebuild_window_surfaces(scaling);
+        }
+
+        // The grid area shrinks or grows whenever the tab bar appears or disappears
+        let tab_bar_height = self.tab_bar_height();
+        let tab_bar_changed = (tab_bar_height - previous_tab_bar_height).abs() > f32::EPSILON;
+
         root_canvas.clear(
             self.default_style
                 .colors
@@ -294,11 +651,15 @@ impl Renderer {
         root_canvas.save();
 
         if let Some(root_window) = self.rendered_windows.get(&1) {
-            let clip_rect = root_window.pixel_region(self.font_width, self.font_height);
+            let clip_rect = offset_region(
+                root_window.pixel_region(self.font_width, self.font_height),
+                tab_bar_height,
+            );
             root_canvas.clip_rect(&clip_rect, None, Some(false));
         }
 
         coordinate_system_helper.use_logical_coordinates(root_canvas);
+        root_canvas.translate((0.0, tab_bar_height));
 
         let default_background = self.get_default_background();
         let font_width = self.font_width;
@@ -311,7 +672,7 @@ impl Renderer {
             ) = self
                 .rendered_windows
                 .values_mut()
-                .filter(|window| !window.hidden)
+                .filter(|window| !window.hidden && window.external.is_none())
                 .partition(|window| !window.floating);
 
             root_windows
@@ -329,14 +690,17 @@ impl Renderer {
         self.window_regions = windows
             .into_iter()
             .map(|window| {
-                window.draw(
+                let mut details = window.draw(
                     root_canvas,
                     &settings,
                     default_background,
                     font_width,
                     font_height,
                     dt,
-                )
+                );
+                // Mouse input is hit tested against window coordinates which include the tab bar
+                details.region = offset_region(details.region, tab_bar_height);
+                details
             })
             .collect();
 
@@ -344,17 +708,77 @@ impl Renderer {
         self.cursor_renderer
             .update_cursor_destination(font_width, font_height, windows);
 
-        self.cursor_renderer.draw(
-            &self.default_style.colors,
-            (self.font_width, self.font_height),
-            &self.current_mode,
-            &mut self.shaper,
-            root_canvas,
-            dt,
-        );
+        // The cursor is drawn by the external window instead when its grid lives in one
+        if !self.cursor_in_external_window() {
+            self.cursor_renderer.draw(
+                &self.default_style.colors,
+                (self.font_width, self.font_height),
+                &self.current_mode,
+                &mut self.shaper,
+                root_canvas,
+                dt,
+            );
+        }
+
+        if let Some(root_window) = self.rendered_windows.get(&1) {
+            let bounds = root_window.pixel_region(self.font_width, self.font_height);
+            if !self.cursor_in_external_window() {
+                self.preedit_renderer.draw(
+                    root_canvas,
+                    &mut self.shaper,
+                    &self.default_style,
+                    self.cursor_renderer.destination(),
+                    bounds,
+                    (self.font_width, self.font_height),
+                );
+            }
+            self.popup_menu_renderer.draw(
+                root_canvas,
+                &mut self.shaper,
+                &self.default_style,
+                bounds,
+                (self.font_width, self.font_height),
+            );
+            self.message_renderer.draw(
+                root_canvas,
+                &mut self.shaper,
+                &self.default_style,
+                bounds,
+                (self.font_width, self.font_height),
+            );
+        }
 
         root_canvas.restore();
 
-        font_changed
+        if let Some(root_window) = self.rendered_windows.get(&1) {
+            let width = root_window
+                .pixel_region(self.font_width, self.font_height)
+                .width();
+            root_canvas.save();
+            coordinate_system_helper.use_logical_coordinates(root_canvas);
+            self.tab_line_renderer.draw(
+                root_canvas,
+                &mut self.shaper,
+                &self.default_style,
+                width,
+                (self.font_width, self.font_height),
+            );
+            root_canvas.restore();
+        }
+
+        if self.reconnecting {
+            if let Some(root_window) = self.rendered_windows.get(&1) {
+                let bounds = offset_region(
+                    root_window.pixel_region(self.font_width, self.font_height),
+                    tab_bar_height,
+                );
+                root_canvas.save();
+                coordinate_system_helper.use_logical_coordinates(root_canvas);
+                self.draw_reconnecting_overlay(root_canvas, bounds);
+                root_canvas.restore();
+            }
+        }
+
+        font_changed || tab_bar_changed
     }
 }
@@ -0,0 +1,455 @@
+use std::sync::Arc;
+
+use skulpin::skia_safe::{image_filters::blur, Canvas, Color, Paint, PaintStyle, Rect};
+
+use super::message_renderer::{line_columns, text_columns, wrap_styled_text};
+use super::CachingShaper;
+use crate::bridge::PopupMenuItem;
+use crate::editor::{blend_alpha, PopupMenuStyles, Style};
+use crate::settings::*;
+
+const CORNER_RADIUS: f32 = 4.0;
+const SHADOW_OFFSET: f32 = 3.0;
+const INFO_GAP: f32 = 4.0;
+
+#[setting_prefix = "popup_menu"]
+#[derive(Clone, SettingGroup)]
+pub struct PopupMenuSettings {
+    max_height: u64,
+    info_width: u64,
+    shadow: bool,
+}
+
+impl Default for PopupMenuSettings {
+    fn default() -> Self {
+        Self {
+            max_height: 15,
+            info_width: 50,
+            shadow: true,
+        }
+    }
+}
+
+struct PopupMenu {
+    items: Vec<PopupMenuItem>,
+    selected: Option<u64>,
+    anchor: (f64, f64),
+    styles: PopupMenuStyles,
+}
+
+// Column offsets for the word, kind and menu columns. Every row is padded by a column on each
+// side and empty kind or menu columns take up no space.
+#[derive(Debug, PartialEq)]
+struct ColumnLayout {
+    kind_start: usize,
+    menu_start: usize,
+    width: usize,
+}
+
+impl ColumnLayout {
+    fn new(items: &[PopupMenuItem]) -> ColumnLayout {
+        let widest = |column: fn(&PopupMenuItem) -> &str| {
+            items
+                .iter()
+                .map(|item| text_columns(column(item)))
+                .max()
+                .unwrap_or(0)
+        };
+        let word = widest(|item| &item.word);
+        let kind = widest(|item| &item.kind);
+        let menu = widest(|item| &item.menu);
+
+        let kind_start = 1 + word + 1;
+        let menu_start = if kind > 0 {
+            kind_start + kind + 1
+        } else {
+            kind_start
+        };
+        let width = if menu > 0 {
+            menu_start + menu + 1
+        } else {
+            menu_start
+        };
+
+        ColumnLayout {
+            kind_start,
+            menu_start,
+            width,
+        }
+    }
+}
+
+// Decides whether the menu should open above or below the anchor row and how many rows fit. The
+// menu prefers opening downwards and only flips when there is more room above.
+fn place_vertically(desired_rows: usize, rows_above: usize, rows_below: usize) -> (bool, usize) {
+    if desired_rows <= rows_below || rows_below >= rows_above {
+        (false, desired_rows.min(rows_below))
+    } else {
+        (true, desired_rows.min(rows_above))
+    }
+}
+
+// Scrolls the menu just enough to keep the selected item visible.
+fn scroll_to_selection(
+    scroll: usize,
+    selected: Option<usize>,
+    visible_rows: usize,
+    item_count: usize,
+) -> usize {
+    let max_scroll = item_count.saturating_sub(visible_rows);
+    let scroll = match selected {
+        Some(selected) if selected < scroll => selected,
+        Some(selected) if selected >= scroll + visible_rows => selected + 1 - visible_rows,
+        _ => scroll,
+    };
+
+    scroll.min(max_scroll)
+}
+
+pub struct PopupMenuRenderer {
+    menu: Option<PopupMenu>,
+    scroll: usize,
+    blend: u8,
+    font_size: (f32, f32),
+}
+
+impl PopupMenuRenderer {
+    pub fn new() -> PopupMenuRenderer {
+        PopupMenuRenderer {
+            menu: None,
+            scroll: 0,
+            blend: 0,
+            font_size: (1.0, 1.0),
+        }
+    }
+
+    pub fn show(
+        &mut self,
+        items: Vec<PopupMenuItem>,
+        selected: Option<u64>,
+        anchor: (f64, f64),
+        styles: PopupMenuStyles,
+    ) {
+        self.menu = Some(PopupMenu {
+            items,
+            selected,
+            anchor,
+            styles,
+        });
+        self.scroll = 0;
+    }
+
+    pub fn select(&mut self, selected: Option<u64>) {
+        if let Some(menu) = &mut self.menu {
+            menu.selected = selected;
+        }
+    }
+
+    pub fn hide(&mut self) {
+        self.menu = None;
+    }
+
+    pub fn set_blend(&mut self, blend: u64) {
+        self.blend = blend.min(100) as u8;
+    }
+
+    fn draw_panel(&self, canvas: &mut Canvas, region: Rect, foreground: Color, background: Color) {
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+
+        if SETTINGS.get::<PopupMenuSettings>().shadow {
+            let shadow_region = Rect::new(
+                region.left + SHADOW_OFFSET,
+                region.top + SHADOW_OFFSET,
+                region.right + SHADOW_OFFSET,
+                region.bottom + SHADOW_OFFSET,
+            );
+            paint.set_color(Color::from_argb(120, 0, 0, 0));
+            paint.set_image_filter(blur((SHADOW_OFFSET, SHADOW_OFFSET), None, None, None));
+            canvas.draw_round_rect(shadow_region, CORNER_RADIUS, CORNER_RADIUS, &paint);
+            paint.set_image_filter(None);
+        }
+
+        paint.set_color(background.with_a(blend_alpha(self.blend)));
+        canvas.draw_round_rect(region, CORNER_RADIUS, CORNER_RADIUS, &paint);
+
+        paint.set_color(foreground.with_a(60));
+        paint.set_style(PaintStyle::Stroke);
+        canvas.draw_round_rect(region, CORNER_RADIUS, CORNER_RADIUS, &paint);
+    }
+
+    fn draw_text(
+        &self,
+        canvas: &mut Canvas,
+        shaper: &mut CachingShaper,
+        text: &str,
+        position: (f32, f32),
+        color: Color,
+    ) {
+        let text = text.trim_end();
+        if text.is_empty() {
+            return;
+        }
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(false);
+        paint.set_color(color);
+
+        for blob in shaper.shape_cached(text, false, false).iter() {
+            canvas.draw_text_blob(blob, position, &paint);
+        }
+    }
+
+    fn draw_info(
+        &self,
+        canvas: &mut Canvas,
+        shaper: &mut CachingShaper,
+        info: &str,
+        menu_region: Rect,
+        bounds: Rect,
+        (foreground, background): (Color, Color),
+    ) {
+        let info_width = SETTINGS.get::<PopupMenuSettings>().info_width as usize;
+        if info_width == 0 || info.trim().is_empty() {
+            return;
+        }
+
+        let (font_width, font_height) = self.font_size;
+        let max_rows = ((bounds.height() / font_height) as usize).saturating_sub(1);
+        let mut lines = wrap_styled_text(&[(info.to_string(), None)], info_width);
+        lines.truncate(max_rows);
+        if lines.is_empty() {
+            return;
+        }
+
+        let columns = lines
+            .iter()
+            .map(|line| line_columns(line))
+            .max()
+            .unwrap_or(0);
+        let width = (columns + 2) as f32 * font_width;
+        let height = (lines.len() + 1) as f32 * font_height;
+
+        // Open the info panel on whichever side of the menu has room for it
+        let mut left = menu_region.right + INFO_GAP;
+        if left + width > bounds.right {
+            left = (menu_region.left - INFO_GAP - width).max(bounds.left);
+        }
+        let top = menu_region.top.min(bounds.bottom - height).max(bounds.top);
+        let region = Rect::new(left, top, left + width, top + height);
+
+        self.draw_panel(canvas, region, foreground, background);
+
+        canvas.save();
+        canvas.clip_rect(region, None, Some(true));
+        for (index, line) in lines.iter().enumerate() {
+            let text: String = line.iter().map(|(text, _)| text.as_str()).collect();
+            let position = (
+                region.left + font_width,
+                region.top + font_height / 2.0 + index as f32 * font_height,
+            );
+            self.draw_text(canvas, shaper, &text, position, foreground);
+        }
+        canvas.restore();
+    }
+
+    pub fn draw(
+        &mut self,
+        canvas: &mut Canvas,
+        shaper: &mut CachingShaper,
+        default_style: &Arc<Style>,
+        bounds: Rect,
+        font_size: (f32, f32),
+    ) {
+        self.font_size = font_size;
+        let menu = match &self.menu {
+            Some(menu) if !menu.items.is_empty() => menu,
+            _ => return,
+        };
+
+        let settings = SETTINGS.get::<PopupMenuSettings>();
+        let (font_width, font_height) = font_size;
+        let bounds_columns = (bounds.width() / font_width) as usize;
+        let bounds_rows = (bounds.height() / font_height) as usize;
+
+        let columns = ColumnLayout::new(&menu.items);
+        let width = columns.width.min(bounds_columns) as f32 * font_width;
+
+        let (anchor_left, anchor_top) = menu.anchor;
+        let anchor_row = (anchor_top.max(0.0) as usize).min(bounds_rows);
+        let desired_rows = menu.items.len().min(settings.max_height.max(1) as usize);
+        let (above, visible_rows) = place_vertically(
+            desired_rows,
+            anchor_row,
+            bounds_rows.saturating_sub(anchor_row + 1),
+        );
+        if visible_rows == 0 {
+            return;
+        }
+        let height = visible_rows as f32 * font_height;
+
+        let selected = menu.selected.map(|selected| selected as usize);
+        self.scroll = scroll_to_selection(self.scroll, selected, visible_rows, menu.items.len());
+
+        // Line the words up with the text being completed by shifting left over the padding
+        let left = (bounds.left + (anchor_left as f32 - 1.0) * font_width)
+            .min(bounds.right - width)
+            .max(bounds.left);
+        let top = if above {
+            bounds.top + anchor_row as f32 * font_height - height
+        } else {
+            bounds.top + (anchor_row + 1) as f32 * font_height
+        };
+        let region = Rect::new(left, top, left + width, top + height);
+
+        // The menu is drawn with the Pmenu highlight groups like neovim's own popup menu
+        let default_colors = &default_style.colors;
+        let normal = menu.styles.normal.as_ref().unwrap_or(default_style);
+        let foreground = normal.foreground(default_colors).to_color();
+        let background = normal.background(default_colors).to_color();
+        self.draw_panel(canvas, region, foreground, background);
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+
+        canvas.save();
+        canvas.clip_rect(region, None, Some(true));
+
+        for row in 0..visible_rows {
+            let index = self.scroll + row;
+            let item = match menu.items.get(index) {
+                Some(item) => item,
+                None => break,
+            };
+            let y = region.top + row as f32 * font_height;
+            let row_region = Rect::new(region.left, y, region.right, y + font_height);
+
+            let mut row_foreground = foreground;
+            if selected == Some(index) {
+                match &menu.styles.selected {
+                    Some(selected_style) => {
+                        let selected_background =
+                            selected_style.background(default_colors).to_color();
+                        paint.set_color(selected_background.with_a(blend_alpha(self.blend)));
+                        row_foreground = selected_style.foreground(default_colors).to_color();
+                    }
+                    None => paint.set_color(foreground.with_a(50)),
+                }
+                canvas.draw_rect(row_region, &paint);
+            }
+
+            self.draw_text(
+                canvas,
+                shaper,
+                &item.word,
+                (region.left + font_width, y),
+                row_foreground,
+            );
+            self.draw_text(
+                canvas,
+                shaper,
+                &item.kind,
+                (region.left + columns.kind_start as f32 * font_width, y),
+                row_foreground.with_a(160),
+            );
+            self.draw_text(
+                canvas,
+                shaper,
+                &item.menu,
+                (region.left + columns.menu_start as f32 * font_width, y),
+                row_foreground.with_a(160),
+            );
+        }
+
+        if menu.items.len() > visible_rows {
+            let thumb_height = height * visible_rows as f32 / menu.items.len() as f32;
+            let thumb_top = region.top + height * self.scroll as f32 / menu.items.len() as f32;
+            let scrollbar_left = region.right - font_width / 3.0;
+
+            if let Some(scrollbar_style) = &menu.styles.scrollbar {
+                paint.set_color(scrollbar_style.background(default_colors).to_color());
+                canvas.draw_rect(
+                    Rect::new(scrollbar_left, region.top, region.right, region.bottom),
+                    &paint,
+                );
+            }
+
+            let thumb_color = match &menu.styles.thumb {
+                Some(thumb_style) => thumb_style.background(default_colors).to_color(),
+                None => foreground.with_a(100),
+            };
+            paint.set_color(thumb_color);
+            canvas.draw_rect(
+                Rect::new(
+                    scrollbar_left,
+                    thumb_top,
+                    region.right,
+                    thumb_top + thumb_height,
+                ),
+                &paint,
+            );
+        }
+
+        canvas.restore();
+
+        if let Some(item) = selected.and_then(|selected| menu.items.get(selected)) {
+            self.draw_info(
+                canvas,
+                shaper,
+                &item.info,
+                region,
+                bounds,
+                (foreground, background),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(word: &str, kind: &str, menu: &str) -> PopupMenuItem {
+        PopupMenuItem {
+            word: word.to_string(),
+            kind: kind.to_string(),
+            menu: menu.to_string(),
+            info: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_column_layout() {
+        let layout = ColumnLayout::new(&[item("foo", "f", "[LS]"), item("barbaz", "", "")]);
+        assert_eq!(
+            layout,
+            ColumnLayout {
+                kind_start: 8,
+                menu_start: 10,
+                width: 15,
+            }
+        );
+    }
+
+    #[test]
+    fn test_column_layout_skips_empty_columns() {
+        let layout = ColumnLayout::new(&[item("foo", "", "")]);
+        assert_eq!(layout.menu_start, layout.kind_start);
+        assert_eq!(layout.width, 5);
+    }
+
+    #[test]
+    fn test_place_vertically() {
+        assert_eq!(place_vertically(5, 2, 10), (false, 5));
+        assert_eq!(place_vertically(5, 10, 2), (true, 5));
+        assert_eq!(place_vertically(5, 3, 3), (false, 3));
+    }
+
+    #[test]
+    fn test_scroll_to_selection() {
+        assert_eq!(scroll_to_selection(0, Some(2), 5, 20), 0);
+        assert_eq!(scroll_to_selection(0, Some(7), 5, 20), 3);
+        assert_eq!(scroll_to_selection(10, Some(4), 5, 20), 4);
+        assert_eq!(scroll_to_selection(10, None, 5, 12), 7);
+    }
+}
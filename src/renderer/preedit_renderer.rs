@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use skulpin::skia_safe::{Canvas, Paint, Point, Rect};
+
+use super::message_renderer::text_columns;
+use super::CachingShaper;
+use crate::editor::Style;
+
+const UNDERLINE_THICKNESS: f32 = 1.0;
+
+// Text an input method is still composing, such as the kana typed before converting them to
+// kanji. Neovim only sees the text once it is committed, so it is drawn over the grid at the
+// cursor until then.
+struct Preedit {
+    text: String,
+    cursor: Option<usize>,
+}
+
+pub struct PreeditRenderer {
+    preedit: Option<Preedit>,
+}
+
+impl PreeditRenderer {
+    pub fn new() -> PreeditRenderer {
+        PreeditRenderer { preedit: None }
+    }
+
+    // The cursor is counted in characters from the start of the text.
+    pub fn set_preedit(&mut self, text: String, cursor: Option<usize>) {
+        self.preedit = if text.is_empty() {
+            None
+        } else {
+            Some(Preedit { text, cursor })
+        };
+    }
+
+    pub fn clear(&mut self) {
+        self.preedit = None;
+    }
+
+    pub fn draw(
+        &self,
+        canvas: &mut Canvas,
+        shaper: &mut CachingShaper,
+        default_style: &Arc<Style>,
+        position: Point,
+        bounds: Rect,
+        font_size: (f32, f32),
+    ) {
+        let preedit = match &self.preedit {
+            Some(preedit) => preedit,
+            None => return,
+        };
+
+        let (font_width, font_height) = font_size;
+        let width = text_columns(&preedit.text) as f32 * font_width;
+        // Long compositions are shifted left rather than running off the edge of the window
+        let left = position.x.min(bounds.right - width).max(bounds.left);
+        let top = position.y;
+
+        let foreground = default_style.colors.foreground.clone().unwrap().to_color();
+        let background = default_style.colors.background.clone().unwrap().to_color();
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(false);
+
+        paint.set_color(background);
+        canvas.draw_rect(
+            Rect::new(left, top, left + width, top + font_height),
+            &paint,
+        );
+
+        paint.set_color(foreground);
+        for blob in shaper.shape_cached(&preedit.text, false, false).iter() {
+            canvas.draw_text_blob(blob, (left, top), &paint);
+        }
+
+        let underline_top = top + font_height - UNDERLINE_THICKNESS;
+        canvas.draw_rect(
+            Rect::new(left, underline_top, left + width, top + font_height),
+            &paint,
+        );
+
+        if let Some(cursor) = preedit.cursor {
+            let before_cursor: String = preedit.text.chars().take(cursor).collect();
+            let cursor_left = left + text_columns(&before_cursor) as f32 * font_width;
+            canvas.draw_rect(
+                Rect::new(
+                    cursor_left,
+                    top,
+                    cursor_left + UNDERLINE_THICKNESS,
+                    top + font_height,
+                ),
+                &paint,
+            );
+        }
+    }
+}
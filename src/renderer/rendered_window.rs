@@ -18,8 +18,6 @@ fn build_window_surface(
     pixel_height: i32,
 ) -> Surface {
     let dimensions = (pixel_width, pixel_height);
-    let mut context = parent_canvas.gpu_context().unwrap();
-    let budgeted = Budgeted::Yes;
     let parent_image_info = parent_canvas.image_info();
     let image_info = ImageInfo::new(
         dimensions,
@@ -27,6 +25,15 @@ fn build_window_surface(
         parent_image_info.alpha_type(),
         parent_image_info.color_space(),
     );
+
+    // Surfaces of grids in external windows live in cpu memory, so resizing them keeps them there
+    let mut context = match parent_canvas.gpu_context() {
+        Some(context) => context,
+        None => {
+            return Surface::new_raster(&image_info, None, None).expect("Could not create surface")
+        }
+    };
+    let budgeted = Budgeted::Yes;
     let surface_origin = SurfaceOrigin::TopLeft;
     Surface::new_render_target(
         &mut context,
@@ -114,6 +121,25 @@ impl SurfacePair {
         }
     }
 
+    // Gpu surfaces can only be drawn by the context that created them, while raster images can be
+    // drawn into the canvas of any window. The contents are read back from the gpu once here
+    // instead of every frame.
+    fn into_raster(self) -> SurfacePair {
+        let copy_to_raster = |mut surface: Surface| {
+            let mut raster_surface = Surface::new_raster(&surface.image_info(), None, None)
+                .expect("Could not create surface");
+            raster_surface.canvas().clear(Color::from_argb(0, 0, 0, 0));
+            surface.draw(raster_surface.canvas(), (0.0, 0.0), None);
+            raster_surface
+        };
+
+        SurfacePair {
+            background: copy_to_raster(self.background),
+            foreground: copy_to_raster(self.foreground),
+            top_line: self.top_line,
+        }
+    }
+
     fn snapshot(&mut self) -> SnapshotPair {
         let background = self.background.image_snapshot();
         let foreground = self.foreground.image_snapshot();
@@ -132,6 +158,9 @@ pub struct RenderedWindow {
     pub id: u64,
     pub hidden: bool,
     pub floating: bool,
+    pub window_handle: Option<u64>,
+    pub external: Option<u64>,
+    blended: bool,
 
     pub grid_width: u64,
     pub grid_height: u64,
@@ -149,10 +178,18 @@ pub struct RenderedWindow {
 
 pub struct WindowDrawDetails {
     pub id: u64,
+    pub window_handle: Option<u64>,
     pub region: Rect,
     pub floating: bool,
 }
 
+pub struct ExternalWindowDetails {
+    pub grid_id: u64,
+    pub window_handle: u64,
+    pub width: f32,
+    pub height: f32,
+}
+
 impl RenderedWindow {
     pub fn new(
         parent_canvas: &mut Canvas,
@@ -178,6 +215,9 @@ impl RenderedWindow {
             id,
             hidden: false,
             floating: false,
+            window_handle: None,
+            external: None,
+            blended: false,
 
             grid_width,
             grid_height,
@@ -206,6 +246,41 @@ impl RenderedWindow {
         Rect::from_point_and_size(current_pixel_position, (image_width, image_height))
     }
 
+    // Cells change size with the font, so the surfaces are rebuilt to fit the grid at the new size.
+    // The old contents are stretched over them until neovim redraws the grid.
+    pub fn rebuild_surfaces(&mut self, renderer: &Renderer, scaling: f32) {
+        let paint = Paint::default();
+
+        let background_snapshot = self.current_surfaces.background.image_snapshot();
+        let mut background = build_background_window_surface(
+            self.current_surfaces.background.canvas(),
+            renderer,
+            self.grid_width,
+            self.grid_height,
+            scaling,
+        );
+        let bounds = Rect::from_wh(background.width() as f32, background.height() as f32);
+        background
+            .canvas()
+            .draw_image_rect(background_snapshot, None, bounds, &paint);
+        self.current_surfaces.background = background;
+
+        let foreground_snapshot = self.current_surfaces.foreground.image_snapshot();
+        let mut foreground = build_window_surface_with_grid_size(
+            self.current_surfaces.foreground.canvas(),
+            renderer,
+            self.grid_width,
+            self.grid_height,
+            scaling,
+        );
+        foreground
+            .canvas()
+            .draw_image_rect(foreground_snapshot, None, bounds, &paint);
+        self.current_surfaces.foreground = foreground;
+
+        self.snapshots.clear();
+    }
+
     pub fn update(&mut self, settings: &RendererSettings, dt: f32) -> bool {
         let mut animating = false;
 
@@ -283,8 +358,10 @@ impl RenderedWindow {
         {
             // Save layer so that setting the blend mode doesn't effect the blur
             root_canvas.save_layer(&SaveLayerRec::default());
+            // Windows with blended highlights get their transparency per cell from neovim, so the
+            // global opacity is only a fallback for floating windows without any
             let mut a = 255;
-            if self.floating {
+            if self.floating && !self.blended {
                 a = (settings.floating_opacity.min(1.0).max(0.0) * 255.0) as u8;
             }
 
@@ -359,11 +436,45 @@ impl RenderedWindow {
 
         WindowDrawDetails {
             id: self.id,
+            window_handle: self.window_handle,
             region: pixel_region,
             floating: self.floating,
         }
     }
 
+    // External windows have their own gpu context, so their grid is kept in raster surfaces which
+    // any context can draw. Skia keeps the uploaded texture until the surface changes.
+    pub fn draw_external(
+        &mut self,
+        canvas: &mut Canvas,
+        settings: &RendererSettings,
+        default_background: Color,
+        font_width: f32,
+        font_height: f32,
+        dt: f32,
+    ) {
+        if self.update(settings, dt) {
+            REDRAW_SCHEDULER.queue_next_frame();
+        }
+
+        canvas.clear(default_background);
+
+        let scroll_offset =
+            self.current_surfaces.top_line * font_height - self.current_scroll * font_height;
+        let region = self
+            .pixel_region(font_width, font_height)
+            .with_offset((0.0, scroll_offset));
+        let paint = Paint::default();
+
+        let snapshots = [
+            self.current_surfaces.background.image_snapshot(),
+            self.current_surfaces.foreground.image_snapshot(),
+        ];
+        for snapshot in snapshots.iter() {
+            canvas.draw_image_rect(snapshot, None, region, &paint);
+        }
+    }
+
     pub fn handle_window_draw_command(
         mut self,
         renderer: &mut Renderer,
@@ -377,6 +488,7 @@ impl RenderedWindow {
                 width: grid_width,
                 height: grid_height,
                 floating,
+                window_handle,
             } => {
                 let new_destination: Point = (grid_left as f32, grid_top as f32).into();
 
@@ -434,6 +546,8 @@ impl RenderedWindow {
                 }
 
                 self.floating = floating;
+                self.window_handle = window_handle;
+                self.external = None;
 
                 if self.hidden {
                     self.hidden = false;
@@ -442,6 +556,37 @@ impl RenderedWindow {
                     self.grid_destination = new_destination;
                 }
             }
+            WindowDrawCommand::External {
+                window_handle,
+                width,
+                height,
+            } => {
+                // External windows are drawn at the origin of their own os window, so they reuse
+                // the resizing logic of a regular position update but never animate
+                let was_external = self.external.is_some();
+                let position = WindowDrawCommand::Position {
+                    grid_left: 0.0,
+                    grid_top: 0.0,
+                    width,
+                    height,
+                    floating: false,
+                    window_handle: Some(window_handle),
+                };
+                self = self.handle_window_draw_command(renderer, position, scaling);
+
+                if !was_external {
+                    self.current_surfaces = self.current_surfaces.into_raster();
+                    self.snapshots.clear();
+                }
+
+                let origin = Point::new(0.0, 0.0);
+                self.external = Some(window_handle);
+                self.hidden = false;
+                self.position_t = 2.0;
+                self.grid_start_position = origin;
+                self.grid_current_position = origin;
+                self.grid_destination = origin;
+            }
             WindowDrawCommand::Cell {
                 text,
                 cell_width,
@@ -451,6 +596,10 @@ impl RenderedWindow {
             } => {
                 let grid_position = (window_left, window_top);
 
+                if style.as_ref().map_or(false, |style| style.blend > 0) {
+                    self.blended = true;
+                }
+
                 {
                     let canvas = self.current_surfaces.background.canvas();
                     canvas.save();
@@ -540,6 +689,7 @@ impl RenderedWindow {
                 );
 
                 self.snapshots.clear();
+                self.blended = false;
             }
             WindowDrawCommand::Show => {
                 if self.hidden {
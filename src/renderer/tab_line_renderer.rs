@@ -0,0 +1,206 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use skulpin::skia_safe::{Canvas, Paint, Rect};
+
+use super::message_renderer::text_columns;
+use super::CachingShaper;
+use crate::bridge::TabInfo;
+use crate::editor::Style;
+
+const TAB_PADDING: f32 = 4.0;
+const INDICATOR_HEIGHT: f32 = 2.0;
+const MIN_TAB_COLUMNS: usize = 8;
+const MAX_TAB_COLUMNS: usize = 30;
+
+// Tabs are labeled with the file name of their current buffer rather than the full path.
+fn tab_title(name: &str) -> String {
+    Path::new(name)
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "[No Name]".to_string())
+}
+
+// Gives every tab room for its title plus padding, shrinking all of them evenly once they no
+// longer fit in the available width. Returns the left and right edge of each tab.
+fn layout_tabs(title_columns: &[usize], font_width: f32, available_width: f32) -> Vec<(f32, f32)> {
+    let widths: Vec<f32> = title_columns
+        .iter()
+        .map(|columns| (columns + 4).max(MIN_TAB_COLUMNS).min(MAX_TAB_COLUMNS) as f32 * font_width)
+        .collect();
+
+    let total_width: f32 = widths.iter().sum();
+    let scale = if total_width > available_width {
+        available_width / total_width
+    } else {
+        1.0
+    };
+
+    let mut left = 0.0;
+    widths
+        .into_iter()
+        .map(|width| {
+            let right = left + width * scale;
+            let edges = (left, right);
+            left = right;
+            edges
+        })
+        .collect()
+}
+
+pub struct TabLineRenderer {
+    tabs: Vec<TabInfo>,
+    current_tab: u64,
+    show_tab_line: u64,
+    tab_regions: Vec<Rect>,
+}
+
+impl TabLineRenderer {
+    pub fn new() -> TabLineRenderer {
+        TabLineRenderer {
+            tabs: Vec::new(),
+            current_tab: 0,
+            show_tab_line: 1,
+            tab_regions: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, current_tab: u64, tabs: Vec<TabInfo>) {
+        self.current_tab = current_tab;
+        self.tabs = tabs;
+    }
+
+    pub fn set_show_tab_line(&mut self, show_tab_line: u64) {
+        self.show_tab_line = show_tab_line;
+    }
+
+    // Mirrors the showtabline option: 0 never shows the tab line, 1 only when there are at least
+    // two tabs and 2 always.
+    fn visible(&self) -> bool {
+        !self.tabs.is_empty()
+            && match self.show_tab_line {
+                0 => false,
+                1 => self.tabs.len() > 1,
+                _ => true,
+            }
+    }
+
+    pub fn height(&self, font_height: f32) -> f32 {
+        if self.visible() {
+            font_height + TAB_PADDING * 2.0
+        } else {
+            0.0
+        }
+    }
+
+    // Returns the one based tab number under the given position, matching the numbering used by
+    // neovim's tab commands.
+    pub fn tab_at(&self, x: f32, y: f32) -> Option<u64> {
+        self.tab_regions
+            .iter()
+            .position(|region| {
+                x >= region.left && x < region.right && y >= region.top && y < region.bottom
+            })
+            .map(|index| index as u64 + 1)
+    }
+
+    pub fn draw(
+        &mut self,
+        canvas: &mut Canvas,
+        shaper: &mut CachingShaper,
+        default_style: &Arc<Style>,
+        width: f32,
+        font_size: (f32, f32),
+    ) {
+        let (font_width, font_height) = font_size;
+        let height = self.height(font_height);
+        if height <= 0.0 {
+            self.tab_regions.clear();
+            return;
+        }
+
+        let background = default_style.colors.background.clone().unwrap().to_color();
+        let foreground = default_style.colors.foreground.clone().unwrap().to_color();
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(false);
+        paint.set_color(background);
+        canvas.draw_rect(Rect::new(0.0, 0.0, width, height), &paint);
+        paint.set_color(foreground.with_a(15));
+        canvas.draw_rect(Rect::new(0.0, 0.0, width, height), &paint);
+
+        let titles: Vec<String> = self.tabs.iter().map(|tab| tab_title(&tab.name)).collect();
+        let title_columns: Vec<usize> = titles.iter().map(|title| text_columns(title)).collect();
+
+        self.tab_regions = layout_tabs(&title_columns, font_width, width)
+            .into_iter()
+            .map(|(left, right)| Rect::new(left, 0.0, right, height))
+            .collect();
+
+        for ((tab, title), region) in self.tabs.iter().zip(titles).zip(self.tab_regions.iter()) {
+            let current = tab.handle == self.current_tab;
+
+            canvas.save();
+            canvas.clip_rect(*region, None, Some(false));
+
+            if current {
+                paint.set_color(background);
+                canvas.draw_rect(*region, &paint);
+                paint.set_color(foreground.with_a(180));
+                canvas.draw_rect(
+                    Rect::new(
+                        region.left,
+                        region.top,
+                        region.right,
+                        region.top + INDICATOR_HEIGHT,
+                    ),
+                    &paint,
+                );
+            }
+
+            paint.set_color(foreground.with_a(40));
+            canvas.draw_rect(
+                Rect::new(region.right - 1.0, region.top, region.right, region.bottom),
+                &paint,
+            );
+
+            paint.set_color(if current {
+                foreground
+            } else {
+                foreground.with_a(140)
+            });
+            for blob in shaper.shape_cached(&title, false, false).iter() {
+                canvas.draw_text_blob(
+                    blob,
+                    (region.left + font_width * 2.0, region.top + TAB_PADDING),
+                    &paint,
+                );
+            }
+
+            canvas.restore();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tab_title() {
+        assert_eq!(tab_title("/home/user/src/main.rs"), "main.rs");
+        assert_eq!(tab_title(""), "[No Name]");
+    }
+
+    #[test]
+    fn test_layout_tabs() {
+        let tabs = layout_tabs(&[2, 10, 40], 1.0, 100.0);
+        assert_eq!(tabs, vec![(0.0, 8.0), (8.0, 22.0), (22.0, 52.0)]);
+    }
+
+    #[test]
+    fn test_layout_tabs_shrinks_to_fit() {
+        let tabs = layout_tabs(&[20, 20], 1.0, 24.0);
+        assert_eq!(tabs, vec![(0.0, 12.0), (12.0, 24.0)]);
+    }
+}
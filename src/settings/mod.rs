@@ -6,13 +6,12 @@ use std::convert::TryInto;
 use flexi_logger::{Cleanup, Criterion, Duplicate, Logger, Naming};
 mod from_value;
 pub use from_value::FromValue;
-use log::warn;
+use log::{error, warn};
 use nvim_rs::Neovim;
 use parking_lot::RwLock;
 pub use rmpv::Value;
 
 use crate::bridge::TxWrapper;
-use crate::error_handling::ResultPanicExplanation;
 
 lazy_static! {
     pub static ref SETTINGS: Settings = Settings::new();
@@ -51,6 +50,10 @@ impl Settings {
                 } else {
                     !(arg.starts_with("--geometry=")
                         || arg.starts_with("--remote-tcp=")
+                        || arg.starts_with("--server=")
+                        || arg.starts_with("--record=")
+                        || arg.starts_with("--replay=")
+                        || arg == "--replay-fast"
                         || arg == "--version"
                         || arg == "-v"
                         || arg == "--help"
@@ -58,6 +61,10 @@ impl Settings {
                         || arg == "--wsl"
                         || arg == "--disowned"
                         || arg == "--multiGrid"
+                        || arg == "--extCmdline"
+                        || arg == "--extMessages"
+                        || arg == "--extPopupmenu"
+                        || arg == "--extTabline"
                         || arg == "--maximized")
                 }
             })
@@ -147,6 +154,9 @@ impl Settings {
         }
     }
 
+    // The watchers notify whichever channel the gui registered in g:neovide_channel_id, which is
+    // only channel 1 for an embedded neovim. Watchers left from an earlier connection to the same
+    // server are removed first so that changes are not reported twice.
     pub async fn setup_changed_listeners(&self, nvim: &Neovim<TxWrapper>) {
         let keys: Vec<String> = self.listeners.read().keys().cloned().collect();
 
@@ -155,18 +165,16 @@ impl Settings {
                 concat!(
                     "exe \"",
                     "fun! NeovideNotify{0}Changed(d, k, z)\n",
-                    "call rpcnotify(1, 'setting_changed', '{0}', g:neovide_{0})\n",
+                    "call rpcnotify(g:neovide_channel_id, 'setting_changed', '{0}', g:neovide_{0})\n",
                     "endf\n",
+                    "silent! call dictwatcherdel(g:, 'neovide_{0}', 'NeovideNotify{0}Changed')\n",
                     "call dictwatcheradd(g:, 'neovide_{0}', 'NeovideNotify{0}Changed')\"",
                 ),
                 name
             );
-            nvim.command(&vimscript)
-                .await
-                .unwrap_or_explained_panic(&format!(
-                    "Could not setup setting notifier for {}",
-                    name
-                ));
+            if let Err(error) = nvim.command(&vimscript).await {
+                error!("Could not setup setting notifier for {}: {}", name, error);
+            }
         }
     }
 
@@ -189,6 +197,7 @@ mod tests {
 
     use super::*;
     use crate::bridge::{create, create_nvim_command};
+    use crate::error_handling::ResultPanicExplanation;
 
     #[derive(Clone)]
     pub struct NeovimHandler();
@@ -0,0 +1,180 @@
+use super::{KeyboardLayout, KeycodeToTokenFn, Modifiers};
+
+/// A keyboard event as reported by the window system, or an input to send to neovim once the
+/// events have been composed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyEvent<T> {
+    /// A key press, sent to neovim as the key's token
+    Down(T, Modifiers),
+
+    /// Text typed by one or more key presses, sent to neovim as is
+    Text(String, Modifiers),
+}
+
+/// Pairs key presses with the text they produce.
+///
+/// Printable keys are followed by a text event when they type something, so they are held back
+/// until the text arrives. A printable key which is followed by another key press without
+/// producing text is a dead key or part of a compose sequence. It is dropped, since the character
+/// it composes arrives as text once the sequence is complete.
+pub struct KeyComposer<T> {
+    text_input: bool,
+    pending: Option<(T, Modifiers)>,
+}
+
+impl<T: Copy> KeyComposer<T> {
+    /// Without text input every key press is sent as its token straight away.
+    pub fn new(text_input: bool) -> KeyComposer<T> {
+        KeyComposer {
+            text_input,
+            pending: None,
+        }
+    }
+
+    pub fn compose(
+        &mut self,
+        event: KeyEvent<T>,
+        layout: &KeyboardLayout,
+        keycode_to_token: KeycodeToTokenFn<T>,
+    ) -> Vec<KeyEvent<T>> {
+        match event {
+            KeyEvent::Down(keycode, modifiers) => {
+                let mut inputs = self.displace_pending();
+                match keycode_to_token(layout, keycode, false) {
+                    // Modifier keys and keys neovim has no name for don't produce any input
+                    None => {}
+                    Some(token)
+                        if self.text_input
+                            && !token.is_special()
+                            && !modifiers.logo
+                            && expects_text(layout, modifiers) =>
+                    {
+                        self.pending = Some((keycode, modifiers));
+                    }
+                    Some(_) => inputs.push(KeyEvent::Down(keycode, modifiers)),
+                }
+                inputs
+            }
+            KeyEvent::Text(text, modifiers) => {
+                // The text carries the modifiers held when its key was pressed, so that alt with
+                // a dead key still sends <M-é>
+                let modifiers = self
+                    .pending
+                    .take()
+                    .map(|(_, pending_modifiers)| pending_modifiers)
+                    .unwrap_or(modifiers);
+                vec![KeyEvent::Text(text, modifiers)]
+            }
+        }
+    }
+
+    /// Called once all events available this frame are composed. A key held with control or alt
+    /// which didn't produce text is a shortcut and is sent as its token, while a key without
+    /// either might be a dead key and keeps waiting.
+    pub fn flush(&mut self) -> Vec<KeyEvent<T>> {
+        match self.pending.take() {
+            Some((keycode, modifiers)) if modifiers.control || modifiers.meta => {
+                vec![KeyEvent::Down(keycode, modifiers)]
+            }
+            pending => {
+                self.pending = pending;
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.pending = None;
+    }
+
+    fn displace_pending(&mut self) -> Vec<KeyEvent<T>> {
+        match self.pending.take() {
+            Some((keycode, modifiers)) if modifiers.control || modifiers.meta => {
+                vec![KeyEvent::Down(keycode, modifiers)]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Whether a key held with these modifiers types text. Control on its own suppresses text, but
+/// control together with alt is how Windows reports AltGr, which does type text.
+pub fn expects_text(layout: &KeyboardLayout, modifiers: Modifiers) -> bool {
+    match layout {
+        KeyboardLayout::Agnostic => !modifiers.control && !modifiers.meta,
+        _ => !modifiers.control || modifiers.meta,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::keyboard::Token;
+
+    const QWERTY: KeyboardLayout = KeyboardLayout::Qwerty;
+
+    fn token(_: &KeyboardLayout, keycode: char, _: bool) -> Option<Token<'static>> {
+        match keycode {
+            '^' => Some(Token::new("^", false, true)),
+            'e' => Some(Token::new("e", false, true)),
+            '\u{1b}' => Some(Token::new("Esc", true, true)),
+            _ => None,
+        }
+    }
+
+    fn modifiers(meta: bool, control: bool) -> Modifiers {
+        Modifiers {
+            shift: false,
+            control,
+            meta,
+            logo: false,
+        }
+    }
+
+    #[test]
+    fn test_dead_key_is_merged_with_text() {
+        let mut composer = KeyComposer::new(true);
+        let none = modifiers(false, false);
+        let mut inputs = Vec::new();
+        inputs.extend(composer.compose(KeyEvent::Down('^', none), &QWERTY, token));
+        inputs.extend(composer.flush());
+        inputs.extend(composer.compose(KeyEvent::Down('e', none), &QWERTY, token));
+        inputs.extend(composer.compose(KeyEvent::Text("ê".to_string(), none), &QWERTY, token));
+        inputs.extend(composer.flush());
+        assert_eq!(inputs, vec![KeyEvent::Text("ê".to_string(), none)]);
+    }
+
+    #[test]
+    fn test_text_keeps_modifiers_of_its_key() {
+        let mut composer = KeyComposer::new(true);
+        let meta = modifiers(true, false);
+        let mut inputs = composer.compose(KeyEvent::Down('e', meta), &QWERTY, token);
+        inputs.extend(composer.compose(
+            KeyEvent::Text("é".to_string(), modifiers(false, false)),
+            &QWERTY,
+            token,
+        ));
+        assert_eq!(inputs, vec![KeyEvent::Text("é".to_string(), meta)]);
+    }
+
+    #[test]
+    fn test_shortcuts_and_special_keys_are_sent() {
+        let mut composer = KeyComposer::new(true);
+        let control = modifiers(false, true);
+        let none = modifiers(false, false);
+        assert_eq!(
+            composer.compose(KeyEvent::Down('\u{1b}', none), &QWERTY, token),
+            vec![KeyEvent::Down('\u{1b}', none)]
+        );
+        assert_eq!(
+            composer.compose(KeyEvent::Down('e', control), &QWERTY, token),
+            vec![KeyEvent::Down('e', control)]
+        );
+
+        let meta = modifiers(true, false);
+        assert!(composer
+            .compose(KeyEvent::Down('e', meta), &QWERTY, token)
+            .is_empty());
+        assert_eq!(composer.flush(), vec![KeyEvent::Down('e', meta)]);
+    }
+}
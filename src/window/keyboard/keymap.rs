@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use crate::settings::{FromValue, Value};
+
+/// Actions handled by the gui itself rather than sent to neovim
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GuiAction {
+    ToggleFullscreen,
+    Copy,
+    Paste,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+}
+
+impl GuiAction {
+    fn from_name(name: &str) -> Option<GuiAction> {
+        match name {
+            "toggle_fullscreen" => Some(GuiAction::ToggleFullscreen),
+            "copy" => Some(GuiAction::Copy),
+            "paste" => Some(GuiAction::Paste),
+            "zoom_in" => Some(GuiAction::ZoomIn),
+            "zoom_out" => Some(GuiAction::ZoomOut),
+            "zoom_reset" => Some(GuiAction::ZoomReset),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            GuiAction::ToggleFullscreen => "toggle_fullscreen",
+            GuiAction::Copy => "copy",
+            GuiAction::Paste => "paste",
+            GuiAction::ZoomIn => "zoom_in",
+            GuiAction::ZoomOut => "zoom_out",
+            GuiAction::ZoomReset => "zoom_reset",
+        }
+    }
+}
+
+// Vim already uses <C-v> for visual block mode and <C-c> to interrupt, so copy and paste are bound
+// to the shortcuts terminals use instead.
+#[cfg(not(target_os = "macos"))]
+const DEFAULT_BINDINGS: &[(&str, GuiAction)] = &[
+    ("<F11>", GuiAction::ToggleFullscreen),
+    ("<C-S-c>", GuiAction::Copy),
+    ("<C-S-v>", GuiAction::Paste),
+    ("<C-=>", GuiAction::ZoomIn),
+    ("<C-->", GuiAction::ZoomOut),
+    ("<C-0>", GuiAction::ZoomReset),
+];
+
+#[cfg(target_os = "macos")]
+const DEFAULT_BINDINGS: &[(&str, GuiAction)] = &[
+    ("<D-C-f>", GuiAction::ToggleFullscreen),
+    ("<D-c>", GuiAction::Copy),
+    ("<D-v>", GuiAction::Paste),
+    ("<D-=>", GuiAction::ZoomIn),
+    ("<D-->", GuiAction::ZoomOut),
+    ("<D-0>", GuiAction::ZoomReset),
+];
+
+/// Maps key notation to gui actions. Keys bound here are never sent to neovim.
+///
+/// The bindings set by the user are merged over the defaults, and binding a key to an empty string
+/// removes its default.
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: HashMap<String, GuiAction>,
+}
+
+impl Keymap {
+    /// Looks up the action bound to a keybinding string produced by `Token::into_string`
+    pub fn action(&self, keybinding: &str) -> Option<GuiAction> {
+        self.bindings.get(&normalize_key(keybinding)).copied()
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: DEFAULT_BINDINGS
+                .iter()
+                .map(|(key, action)| (normalize_key(key), *action))
+                .collect(),
+        }
+    }
+}
+
+impl FromValue for Keymap {
+    fn from_value(&mut self, value: Value) {
+        let entries = match value.as_map() {
+            Some(entries) => entries,
+            None => {
+                log::error!(
+                    "keymap setting expected a dictionary, but received: {}",
+                    value
+                );
+                return;
+            }
+        };
+
+        let mut keymap = Keymap::default();
+        for (key, action) in entries.iter() {
+            match (key.as_str(), action.as_str()) {
+                (Some(key), Some("")) => {
+                    keymap.bindings.remove(&normalize_key(key));
+                }
+                (Some(key), Some(name)) => match GuiAction::from_name(name) {
+                    Some(action) => {
+                        keymap.bindings.insert(normalize_key(key), action);
+                    }
+                    None => log::error!("keymap setting received an unknown action: {}", name),
+                },
+                _ => log::error!(
+                    "keymap setting expected key notation mapped to an action name, but received: {} -> {}",
+                    key,
+                    action
+                ),
+            }
+        }
+        *self = keymap;
+    }
+}
+
+impl From<Keymap> for Value {
+    fn from(keymap: Keymap) -> Self {
+        Value::Map(
+            keymap
+                .bindings
+                .into_iter()
+                .map(|(key, action)| (key.into(), action.name().into()))
+                .collect(),
+        )
+    }
+}
+
+// Vim accepts modifiers in any order and key names in any case, so both the bindings and the keys
+// looked up are brought into the order `Token::into_string` produces them. Shift with a letter is
+// written with the lower case letter, as the tokens do.
+fn normalize_key(key: &str) -> String {
+    let inner = match key.strip_prefix('<').and_then(|key| key.strip_suffix('>')) {
+        Some(inner) if !inner.is_empty() => inner,
+        _ => return key.to_string(),
+    };
+
+    let (modifiers, name) = match inner.strip_suffix('-') {
+        // The minus key itself, as in <C-->
+        Some(modifiers) if modifiers.is_empty() || modifiers.ends_with('-') => {
+            (modifiers.trim_end_matches('-'), "-")
+        }
+        _ => match inner.rfind('-') {
+            Some(index) => (&inner[..index], &inner[index + 1..]),
+            None => ("", inner),
+        },
+    };
+
+    let mut logo = false;
+    let mut shift = false;
+    let mut control = false;
+    let mut meta = false;
+    for modifier in modifiers.split('-').filter(|modifier| !modifier.is_empty()) {
+        match modifier.to_ascii_uppercase().as_str() {
+            "D" => logo = true,
+            "S" => shift = true,
+            "C" => control = true,
+            "M" | "A" => meta = true,
+            _ => return key.to_string(),
+        }
+    }
+
+    let name = if name.chars().count() > 1 || shift {
+        name.to_ascii_lowercase()
+    } else {
+        name.to_string()
+    };
+
+    format!(
+        "<{}{}{}{}{}>",
+        if logo { "D-" } else { "" },
+        if shift { "S-" } else { "" },
+        if control { "C-" } else { "" },
+        if meta { "M-" } else { "" },
+        name
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_key() {
+        assert_eq!(normalize_key("<C-S-v>"), "<S-C-v>");
+        assert_eq!(normalize_key("<c-s-V>"), "<S-C-v>");
+        assert_eq!(normalize_key("<A-D-x>"), "<D-M-x>");
+        assert_eq!(normalize_key("<f11>"), normalize_key("<F11>"));
+        assert_eq!(normalize_key("<C-->"), "<C-->");
+        assert_eq!(normalize_key("<C-V>"), "<C-V>");
+        assert_eq!(normalize_key("x"), "x");
+    }
+
+    #[test]
+    fn test_keymap_merges_over_defaults() {
+        let mut keymap = Keymap::default();
+        let (default_key, default_action) = DEFAULT_BINDINGS[0];
+        keymap.from_value(Value::Map(vec![
+            ("<C-=>".into(), "toggle_fullscreen".into()),
+            (default_key.into(), "".into()),
+        ]));
+
+        assert_eq!(keymap.action("<C-=>"), Some(GuiAction::ToggleFullscreen));
+        assert_ne!(keymap.action(default_key), Some(default_action));
+        let (other_key, other_action) = DEFAULT_BINDINGS[1];
+        assert_eq!(keymap.action(other_key), Some(other_action));
+    }
+}
@@ -3,12 +3,21 @@ use crate::settings::{FromValue, Value};
 #[derive(Clone)]
 pub enum KeyboardLayout {
     Qwerty,
+    Azerty,
+    Qwertz,
+    Dvorak,
+    /// Trusts the text the os produces for printable keys instead of a layout table
+    Agnostic,
 }
 
 impl FromValue for KeyboardLayout {
     fn from_value(&mut self, value: Value) {
         match value.as_str() {
             Some("qwerty") => *self = KeyboardLayout::Qwerty,
+            Some("azerty") => *self = KeyboardLayout::Azerty,
+            Some("qwertz") => *self = KeyboardLayout::Qwertz,
+            Some("dvorak") => *self = KeyboardLayout::Dvorak,
+            Some("agnostic") => *self = KeyboardLayout::Agnostic,
             _ => log::error!(
                 "keyboard_layout setting expected a known keyboard layout name, but received: {}",
                 value
@@ -21,6 +30,10 @@ impl From<KeyboardLayout> for Value {
     fn from(layout: KeyboardLayout) -> Self {
         match layout {
             KeyboardLayout::Qwerty => "qwerty".into(),
+            KeyboardLayout::Azerty => "azerty".into(),
+            KeyboardLayout::Qwertz => "qwertz".into(),
+            KeyboardLayout::Dvorak => "dvorak".into(),
+            KeyboardLayout::Agnostic => "agnostic".into(),
         }
     }
 }
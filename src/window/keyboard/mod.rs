@@ -1,3 +1,5 @@
+mod composer;
+mod keymap;
 mod layout;
 mod modifiers;
 mod settings;
@@ -6,10 +8,15 @@ mod token;
 use crate::settings::SETTINGS;
 
 pub use self::{
-    layout::KeyboardLayout, modifiers::Modifiers, settings::KeyboardSettings, token::Token,
+    composer::{expects_text, KeyComposer, KeyEvent},
+    keymap::{GuiAction, Keymap},
+    layout::KeyboardLayout,
+    modifiers::Modifiers,
+    settings::KeyboardSettings,
+    token::Token,
 };
 
-type KeycodeToTokenFn<T> = fn(T, bool) -> Option<Token<'static>>;
+type KeycodeToTokenFn<T> = fn(&KeyboardLayout, T, bool) -> Option<Token<'static>>;
 
 pub fn neovim_keybinding_string<T, U>(
     keycode: Option<U>,
@@ -31,10 +38,20 @@ where
             .into_string(modifiers),
         )
     } else if let Some(keycode) = keycode {
-        match SETTINGS.get::<KeyboardSettings>().layout {
-            KeyboardLayout::Qwerty => keycode_to_token(keycode, modifiers.shift),
+        let layout = SETTINGS.get::<KeyboardSettings>().keyboard_layout;
+        if let KeyboardLayout::Agnostic = layout {
+            // Rather than guessing which symbol shift produces, the key's own character is sent
+            // with an explicit shift modifier. Printable keys without modifiers are left to the
+            // text input the os sends for them.
+            keycode_to_token(&layout, keycode, false)
+                .filter(|token| {
+                    token.is_special() || modifiers.control || modifiers.meta || modifiers.logo
+                })
+                .map(|token| token.with_shift().into_string(modifiers))
+        } else {
+            keycode_to_token(&layout, keycode, modifiers.shift)
+                .map(|token| token.into_string(modifiers))
         }
-        .map(|e| e.into_string(modifiers))
     } else {
         None
     }
@@ -1,5 +1,7 @@
+use super::token::use_logo;
+
 /// The keyboard modifiers associated with a keystroke
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Modifiers {
     /// Shift key
     pub shift: bool,
@@ -13,3 +15,14 @@ pub struct Modifiers {
     /// Windows key on PC, command key on Mac
     pub logo: bool,
 }
+
+impl Modifiers {
+    /// Formats the modifiers the way `nvim_input_mouse` expects them, such as "S-C-"
+    pub fn mouse_string(self) -> String {
+        let command = if use_logo(self.logo) { "D-" } else { "" };
+        let shift = if self.shift { "S-" } else { "" };
+        let control = if self.control { "C-" } else { "" };
+        let meta = if self.meta { "M-" } else { "" };
+        format!("{}{}{}{}", command, shift, control, meta)
+    }
+}
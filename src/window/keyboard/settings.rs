@@ -1,16 +1,17 @@
-use super::KeyboardLayout;
+use super::{KeyboardLayout, Keymap};
 use crate::settings::FromValue;
 
-#[setting_prefix = "keyboard"]
 #[derive(Clone, SettingGroup)]
 pub struct KeyboardSettings {
-    pub layout: KeyboardLayout,
+    pub keyboard_layout: KeyboardLayout,
+    pub keymap: Keymap,
 }
 
 impl Default for KeyboardSettings {
     fn default() -> Self {
         Self {
-            layout: KeyboardLayout::Qwerty,
+            keyboard_layout: KeyboardLayout::Qwerty,
+            keymap: Keymap::default(),
         }
     }
 }
@@ -23,6 +23,19 @@ impl<'a> Token<'a> {
         }
     }
 
+    /// Whether the token is a named key such as <Esc> rather than a printable character
+    pub fn is_special(&self) -> bool {
+        self.special
+    }
+
+    /// Makes the token include the shift modifier when shift is held
+    pub fn with_shift(self) -> Self {
+        Self {
+            use_shift: true,
+            ..self
+        }
+    }
+
     /// Converts the keypress to a Neovim input
     pub fn into_string(self, mods: Modifiers) -> String {
         let shift = self.use_shift && mods.shift;
@@ -41,13 +54,38 @@ impl<'a> Token<'a> {
 }
 
 #[cfg(not(target_os = "windows"))]
-fn use_logo(logo: bool) -> bool {
+pub(super) fn use_logo(logo: bool) -> bool {
     logo
 }
 
 // The Windows key is used for OS-level shortcuts,
 // so we want to ignore the logo key on this platform.
 #[cfg(target_os = "windows")]
-fn use_logo(_: bool) -> bool {
+pub(super) fn use_logo(_: bool) -> bool {
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_shift() {
+        let modifiers = Modifiers {
+            shift: true,
+            control: true,
+            meta: false,
+            logo: false,
+        };
+        assert_eq!(
+            Token::new("1", false, false).into_string(modifiers),
+            "<C-1>"
+        );
+        assert_eq!(
+            Token::new("1", false, false)
+                .with_shift()
+                .into_string(modifiers),
+            "<S-C-1>"
+        );
+    }
+}
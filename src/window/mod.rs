@@ -6,13 +6,18 @@ mod settings;
 mod window_wrapper;
 
 use crate::{
-    bridge::UiCommand,
+    bridge::{recorded_grid_size, UiCommand},
+    clipboard,
     editor::{DrawCommand, WindowCommand},
+    redraw_scheduler::REDRAW_SCHEDULER,
     renderer::Renderer,
+    settings::SETTINGS,
     INITIAL_DIMENSIONS,
 };
 use crossfire::mpsc::TxUnbounded;
-use skulpin::LogicalSize;
+use keyboard::GuiAction;
+use log::error;
+use skulpin::{CoordinateSystem, LogicalSize, PresentMode, RendererBuilder};
 use std::sync::{atomic::AtomicBool, mpsc::Receiver, Arc};
 
 #[cfg(feature = "sdl2")]
@@ -22,22 +27,28 @@ pub use window_wrapper::start_loop;
 
 pub use settings::*;
 
+const ZOOM_STEP: f32 = 1.1;
+const MIN_SCALE_FACTOR: f32 = 0.25;
+const MAX_SCALE_FACTOR: f32 = 4.0;
+
 pub fn window_geometry() -> Result<(u64, u64), String> {
     let prefix = "--geometry=";
 
     std::env::args()
         .find(|arg| arg.starts_with(prefix))
-        .map_or(Ok(INITIAL_DIMENSIONS), |arg| {
-            let input = &arg[prefix.len()..];
-            let invalid_parse_err = format!(
-                "Invalid geometry: {}\nValid format: <width>x<height>",
-                input
-            );
+        .map_or_else(
+            || Ok(recorded_grid_size().unwrap_or(INITIAL_DIMENSIONS)),
+            |arg| {
+                let input = &arg[prefix.len()..];
+                let invalid_parse_err = format!(
+                    "Invalid geometry: {}\nValid format: <width>x<height>",
+                    input
+                );
 
-            input
-                .split('x')
-                .map(|dimension| {
-                    dimension
+                input
+                    .split('x')
+                    .map(|dimension| {
+                        dimension
                         .parse::<u64>()
                         .map_err(|_| invalid_parse_err.as_str())
                         .and_then(|dimension| {
@@ -47,17 +58,18 @@ pub fn window_geometry() -> Result<(u64, u64), String> {
                                 Err("Invalid geometry: Window dimensions should be greater than 0.")
                             }
                         })
-                })
-                .collect::<Result<Vec<_>, &str>>()
-                .and_then(|dimensions| {
-                    if let [width, height] = dimensions[..] {
-                        Ok((width, height))
-                    } else {
-                        Err(invalid_parse_err.as_str())
-                    }
-                })
-                .map_err(|msg| msg.to_owned())
-        })
+                    })
+                    .collect::<Result<Vec<_>, &str>>()
+                    .and_then(|dimensions| {
+                        if let [width, height] = dimensions[..] {
+                            Ok((width, height))
+                        } else {
+                            Err(invalid_parse_err.as_str())
+                        }
+                    })
+                    .map_err(|msg| msg.to_owned())
+            },
+        )
 }
 
 pub fn window_geometry_or_default() -> (u64, u64) {
@@ -80,9 +92,12 @@ fn handle_new_grid_size(
     ui_command_sender: &TxUnbounded<UiCommand>,
 ) {
     if new_size.width > 0 && new_size.height > 0 {
+        // The tab bar is drawn above the grids so it is not part of the grid area
+        let grid_area_height = (new_size.height as f32 - renderer.tab_bar_height()).max(0.0);
+
         // Add 1 here to make sure resizing doesn't change the grid size on startup
         let new_width = ((new_size.width + 1) as f32 / renderer.font_width) as u32;
-        let new_height = ((new_size.height + 1) as f32 / renderer.font_height) as u32;
+        let new_height = ((grid_area_height + 1.0) / renderer.font_height) as u32;
         ui_command_sender
             .send(UiCommand::Resize {
                 width: new_width,
@@ -92,6 +107,67 @@ fn handle_new_grid_size(
     }
 }
 
+// External windows hold a single grid, so their whole area is given to it.
+fn handle_new_external_grid_size(
+    grid_id: u64,
+    new_size: LogicalSize,
+    renderer: &Renderer,
+    ui_command_sender: &TxUnbounded<UiCommand>,
+) {
+    if new_size.width > 0 && new_size.height > 0 {
+        let new_width = ((new_size.width + 1) as f32 / renderer.font_width) as u32;
+        let new_height = ((new_size.height + 1) as f32 / renderer.font_height) as u32;
+        ui_command_sender
+            .send(UiCommand::ResizeGrid {
+                grid_id,
+                width: new_width,
+                height: new_height,
+            })
+            .ok();
+    }
+}
+
+// The os windows grids are moved into with win_external_pos draw from cpu memory, so they don't
+// need the gpu of the main window and are set up the same way on every backend.
+fn external_window_renderer_builder() -> RendererBuilder {
+    RendererBuilder::new()
+        .prefer_discrete_gpu()
+        .use_vulkan_debug_layer(false)
+        .present_mode_priority(vec![PresentMode::Immediate])
+        .coordinate_system(CoordinateSystem::Logical)
+}
+
+fn handle_paste(ui_command_sender: &TxUnbounded<UiCommand>) {
+    match clipboard::get_contents() {
+        Ok(contents) if !contents.is_empty() => {
+            ui_command_sender.send(UiCommand::Paste(contents)).ok();
+        }
+        Ok(_) => {}
+        Err(error) => error!("Could not read the clipboard: {}", error),
+    }
+}
+
+// The renderer picks the new scale factor up on the next frame, which also works while a remote
+// connection is down. It is then stored in g:neovide_scale_factor so that scripts reading it stay
+// in sync.
+fn handle_zoom(action: GuiAction, ui_command_sender: &TxUnbounded<UiCommand>) {
+    let scale_factor = SETTINGS.get::<WindowSettings>().scale_factor;
+    let scale_factor = match action {
+        GuiAction::ZoomIn => scale_factor * ZOOM_STEP,
+        GuiAction::ZoomOut => scale_factor / ZOOM_STEP,
+        _ => 1.0,
+    };
+    let scale_factor = scale_factor.max(MIN_SCALE_FACTOR).min(MAX_SCALE_FACTOR);
+    SETTINGS.set(&WindowSettings {
+        scale_factor,
+        ..SETTINGS.get::<WindowSettings>()
+    });
+    REDRAW_SCHEDULER.queue_next_frame();
+    ui_command_sender
+        .send(UiCommand::SetScaleFactor(scale_factor))
+        .ok();
+}
+
 pub fn create_window(
     batched_draw_command_receiver: Receiver<Vec<DrawCommand>>,
     window_command_receiver: Receiver<WindowCommand>,
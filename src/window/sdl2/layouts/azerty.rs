@@ -0,0 +1,55 @@
+use super::handle_qwerty_layout;
+use crate::window::keyboard::Token;
+use skulpin::sdl2::keyboard::Keycode::{self, *};
+
+/// Maps sdl keyboard events on a French AZERTY layout to Vim tokens. Sdl keycodes already name
+/// the character a key types without shift, so only the shifted symbols differ from qwerty.
+pub fn handle_azerty_layout(keycode: Keycode, shift: bool) -> Option<Token<'static>> {
+    let special = |text| Some(Token::new(text, true, true));
+    let partial = |text| Some(Token::new(text, false, false));
+    match (keycode, shift) {
+        // Depending on the platform, sdl reports the number row either by the digit or by the
+        // symbol printed on the key
+        (Num1, false) | (Ampersand, false) => partial("&"),
+        (Num1, true) | (Ampersand, true) => partial("1"),
+        (Num2, false) => partial("é"),
+        (Num2, true) => partial("2"),
+        (Num3, false) | (Quotedbl, false) => partial("\""),
+        (Num3, true) | (Quotedbl, true) => partial("3"),
+        (Num4, false) | (Quote, false) => partial("'"),
+        (Num4, true) | (Quote, true) => partial("4"),
+        (Num5, false) | (LeftParen, false) => partial("("),
+        (Num5, true) | (LeftParen, true) => partial("5"),
+        (Num6, false) | (Minus, false) => partial("-"),
+        (Num6, true) | (Minus, true) => partial("6"),
+        (Num7, false) => partial("è"),
+        (Num7, true) => partial("7"),
+        (Num8, false) | (Underscore, false) => partial("_"),
+        (Num8, true) | (Underscore, true) => partial("8"),
+        (Num9, false) => partial("ç"),
+        (Num9, true) => partial("9"),
+        (Num0, false) => partial("à"),
+        (Num0, true) => partial("0"),
+        (RightParen, false) => partial(")"),
+        (RightParen, true) => partial("°"),
+        (Equals, false) => partial("="),
+        (Equals, true) => partial("+"),
+        (Caret, false) => partial("^"),
+        (Caret, true) => partial("¨"),
+        (Dollar, false) => partial("$"),
+        (Dollar, true) => partial("£"),
+        (Asterisk, false) => partial("*"),
+        (Asterisk, true) => partial("µ"),
+        (Comma, false) => partial(","),
+        (Comma, true) => partial("?"),
+        (Semicolon, false) => partial(";"),
+        (Semicolon, true) => partial("."),
+        (Colon, false) => partial(":"),
+        (Colon, true) => partial("/"),
+        (Exclaim, false) => partial("!"),
+        (Exclaim, true) => partial("§"),
+        (Less, false) => special("lt"),
+        (Less, true) => partial(">"),
+        (keycode, shift) => handle_qwerty_layout(keycode, shift),
+    }
+}
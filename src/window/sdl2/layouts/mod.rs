@@ -1,9 +1,33 @@
+mod azerty;
 mod qwerty;
+mod qwertz;
 
-use crate::{settings::SETTINGS, window::keyboard::Modifiers, WindowSettings};
-use skulpin::sdl2::keyboard::Mod;
+use crate::{
+    settings::SETTINGS,
+    window::keyboard::{KeyboardLayout, Modifiers, Token},
+    WindowSettings,
+};
+use skulpin::sdl2::keyboard::{Keycode, Mod};
 
-pub use qwerty::handle_qwerty_layout;
+use azerty::handle_azerty_layout;
+use qwerty::handle_qwerty_layout;
+use qwertz::handle_qwertz_layout;
+
+pub fn handle_keyboard_layout(
+    layout: &KeyboardLayout,
+    keycode: Keycode,
+    shift: bool,
+) -> Option<Token<'static>> {
+    match layout {
+        KeyboardLayout::Azerty => handle_azerty_layout(keycode, shift),
+        KeyboardLayout::Qwertz => handle_qwertz_layout(keycode, shift),
+        // Sdl keycodes follow the active layout and US Dvorak pairs every symbol with the same
+        // shifted symbol as US QWERTY, just on a different key.
+        KeyboardLayout::Qwerty | KeyboardLayout::Dvorak | KeyboardLayout::Agnostic => {
+            handle_qwerty_layout(keycode, shift)
+        }
+    }
+}
 
 impl From<Mod> for Modifiers {
     fn from(mods: Mod) -> Modifiers {
@@ -0,0 +1,37 @@
+use super::handle_qwerty_layout;
+use crate::window::keyboard::Token;
+use skulpin::sdl2::keyboard::Keycode::{self, *};
+
+/// Maps sdl keyboard events on a German QWERTZ layout to Vim tokens. Sdl keycodes already name
+/// the character a key types without shift, so only the shifted symbols differ from qwerty.
+pub fn handle_qwertz_layout(keycode: Keycode, shift: bool) -> Option<Token<'static>> {
+    let special = |text| Some(Token::new(text, true, true));
+    let partial = |text| Some(Token::new(text, false, false));
+    match (keycode, shift) {
+        (Num1, true) => partial("!"),
+        (Num2, true) => partial("\""),
+        (Num3, true) => partial("§"),
+        (Num4, true) => partial("$"),
+        (Num5, true) => partial("%"),
+        (Num6, true) => partial("&"),
+        (Num7, true) => partial("/"),
+        (Num8, true) => partial("("),
+        (Num9, true) => partial(")"),
+        (Num0, true) => partial("="),
+        (Plus, false) => partial("+"),
+        (Plus, true) => partial("*"),
+        (Hash, false) => partial("#"),
+        (Hash, true) => partial("'"),
+        (Minus, false) => partial("-"),
+        (Minus, true) => partial("_"),
+        (Period, false) => partial("."),
+        (Period, true) => partial(":"),
+        (Comma, false) => partial(","),
+        (Comma, true) => partial(";"),
+        (Less, false) => special("lt"),
+        (Less, true) => partial(">"),
+        (Caret, false) => partial("^"),
+        (Caret, true) => partial("°"),
+        (keycode, shift) => handle_qwerty_layout(keycode, shift),
+    }
+}
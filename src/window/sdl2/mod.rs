@@ -1,20 +1,33 @@
 #[macro_use]
 mod layouts;
 
-use super::{handle_new_grid_size, keyboard::neovim_keybinding_string, WindowSettings};
+use super::{
+    external_window_renderer_builder, handle_new_external_grid_size, handle_new_grid_size,
+    handle_paste, handle_zoom,
+    keyboard::{
+        neovim_keybinding_string, GuiAction, KeyComposer, KeyEvent, KeyboardSettings, Modifiers,
+    },
+    WindowSettings,
+};
 use crate::{
-    bridge::UiCommand, editor::WindowCommand, error_handling::ResultPanicExplanation,
-    redraw_scheduler::REDRAW_SCHEDULER, renderer::Renderer, settings::SETTINGS,
+    bridge::UiCommand,
+    editor::WindowCommand,
+    error_handling::ResultPanicExplanation,
+    redraw_scheduler::REDRAW_SCHEDULER,
+    renderer::{ExternalWindowDetails, Renderer},
+    settings::SETTINGS,
 };
 use crossfire::mpsc::TxUnbounded;
 use image::load_from_memory_with_format;
-use layouts::handle_qwerty_layout;
+use layouts::handle_keyboard_layout;
 use skulpin::{
     ash::prelude::VkResult,
     sdl2::{
         self,
         event::{Event, WindowEvent},
         keyboard::Keycode,
+        mouse::MouseButton,
+        rect::Rect,
         video::FullscreenType,
         EventPump, Sdl,
     },
@@ -22,6 +35,7 @@ use skulpin::{
     RendererBuilder, Sdl2Window, Window,
 };
 use std::{
+    collections::HashMap,
     sync::{
         atomic::{AtomicBool, Ordering},
         mpsc::Receiver,
@@ -35,16 +49,32 @@ use std::{
 #[folder = "assets/"]
 struct Asset;
 
+// An os window holding a single grid which neovim has moved out of the main window.
+struct Sdl2ExternalWindow {
+    window: sdl2::video::Window,
+    skulpin_renderer: SkulpinRenderer,
+    window_handle: u64,
+    previous_size: LogicalSize,
+}
+
 pub struct Sdl2WindowWrapper {
     context: Sdl,
     window: sdl2::video::Window,
     skulpin_renderer: SkulpinRenderer,
     event_pump: EventPump,
     renderer: Renderer,
-    mouse_down: bool,
+    pressed_button: Option<&'static str>,
+    pointer_position: LogicalSize,
+    dropped_files: Vec<String>,
+    drop_target: Option<u64>,
+    ime_region: Option<Rect>,
+    key_composer: KeyComposer<Keycode>,
     mouse_position: LogicalSize,
     mouse_enabled: bool,
     grid_id_under_mouse: u64,
+    tab_under_mouse: Option<u64>,
+    dragged_tab: Option<u64>,
+    external_windows: HashMap<u64, Sdl2ExternalWindow>,
     title: String,
     previous_size: LogicalSize,
     transparency: f32,
@@ -133,25 +163,106 @@ impl Sdl2WindowWrapper {
             .expect("Could not set title");
     }
 
+    // The bridge decides how to shut down since remote sessions should only be detached from
     pub fn handle_quit(&mut self) {
-        self.running.store(false, Ordering::Relaxed);
+        if self.ui_command_sender.send(UiCommand::Quit).is_err() {
+            self.running.store(false, Ordering::Relaxed);
+        }
     }
 
-    pub fn handle_keyboard_input(&mut self, keycode: Option<Keycode>, text: Option<String>) {
-        let modifiers = self.context.keyboard().mod_state();
+    fn external_grid_id(&self, window_id: u32) -> Option<u64> {
+        self.external_windows
+            .iter()
+            .find(|(_, external_window)| external_window.window.id() == window_id)
+            .map(|(grid_id, _)| *grid_id)
+    }
+
+    // Closing the main window quits, but closing an external window only closes the neovim
+    // window shown in it.
+    pub fn handle_window_close(&mut self, window_id: u32) {
+        if let Some(grid_id) = self.external_grid_id(window_id) {
+            let window_handle = self.external_windows[&grid_id].window_handle;
+            self.ui_command_sender
+                .send(UiCommand::CloseWindow(window_handle))
+                .ok();
+        } else if window_id == self.window.id() {
+            self.handle_quit();
+        }
+    }
+
+    // The os doesn't say where files were dropped, so the last known pointer position is hit
+    // tested against the window regions to find the split they were dropped onto.
+    pub fn handle_file_drop(&mut self, window_id: u32, path: String) {
+        if self.dropped_files.is_empty() {
+            self.drop_target = match self.external_grid_id(window_id) {
+                Some(grid_id) => Some(self.external_windows[&grid_id].window_handle),
+                None => self
+                    .renderer
+                    .window_region_at(self.pointer_position.width, self.pointer_position.height)
+                    .and_then(|details| details.window_handle),
+            };
+        }
+        self.dropped_files.push(path);
+    }
 
-        if keycode.is_some() || text.is_some() {
-            log::trace!(
-                "Keyboard Input Received: keycode-{:?} modifiers-{:?} text-{:?}",
-                keycode,
-                modifiers,
-                text
+    // Each dropped file arrives as its own event, so they are collected and opened together once
+    // the drop is complete.
+    pub fn handle_drop_complete(&mut self) {
+        if self.dropped_files.is_empty() {
+            return;
+        }
+
+        self.ui_command_sender
+            .send(UiCommand::FileDrop {
+                paths: std::mem::take(&mut self.dropped_files),
+                window_handle: self.drop_target.take(),
+                mode: SETTINGS.get::<WindowSettings>().file_drop,
+            })
+            .ok();
+    }
+
+    pub fn handle_keyboard_events(&mut self, events: Vec<KeyEvent<Keycode>>) {
+        let layout = SETTINGS.get::<KeyboardSettings>().keyboard_layout;
+        let mut inputs = Vec::new();
+        for event in events.into_iter() {
+            log::trace!("Keyboard Input Received: {:?}", event);
+            inputs.extend(
+                self.key_composer
+                    .compose(event, &layout, handle_keyboard_layout),
             );
         }
+        inputs.extend(self.key_composer.flush());
+
+        for input in inputs.into_iter() {
+            self.send_keyboard_input(input);
+        }
+    }
+
+    fn send_keyboard_input(&mut self, input: KeyEvent<Keycode>) {
+        let keybinding_string = match input {
+            KeyEvent::Down(keycode, modifiers) => {
+                neovim_keybinding_string(Some(keycode), None, modifiers, handle_keyboard_layout)
+            }
+            KeyEvent::Text(text, modifiers) => {
+                neovim_keybinding_string(None, Some(text), modifiers, handle_keyboard_layout)
+            }
+        };
+
+        if let Some(keybinding_string) = keybinding_string {
+            let keymap = SETTINGS.get::<KeyboardSettings>().keymap;
+            if let Some(action) = keymap.action(&keybinding_string) {
+                self.handle_gui_action(action);
+                return;
+            }
+
+            // Any key press dismisses the message history the same way it would in the terminal,
+            // and like the more-prompt the key is not passed on to neovim
+            if self.renderer.message_history_visible() {
+                self.renderer.hide_message_history();
+                REDRAW_SCHEDULER.queue_next_frame();
+                return;
+            }
 
-        if let Some(keybinding_string) =
-            neovim_keybinding_string(keycode, text, modifiers, handle_qwerty_layout)
-        {
             self.ui_command_sender
                 .send(UiCommand::Keyboard(keybinding_string))
                 .unwrap_or_explained_panic(
@@ -160,41 +271,62 @@ impl Sdl2WindowWrapper {
         }
     }
 
+    pub fn handle_gui_action(&mut self, action: GuiAction) {
+        log::trace!("Gui Action: {:?}", action);
+        match action {
+            GuiAction::ToggleFullscreen => self.toggle_fullscreen(),
+            GuiAction::Copy => {
+                self.ui_command_sender.send(UiCommand::Copy).ok();
+            }
+            GuiAction::Paste => handle_paste(&self.ui_command_sender),
+            GuiAction::ZoomIn | GuiAction::ZoomOut | GuiAction::ZoomReset => {
+                handle_zoom(action, &self.ui_command_sender)
+            }
+        }
+    }
+
     pub fn handle_pointer_motion(&mut self, x: i32, y: i32) {
         let previous_position = self.mouse_position;
         let sdl_window_wrapper = Sdl2Window::new(&self.window);
         let logical_position =
             PhysicalSize::new(x as u32, y as u32).to_logical(sdl_window_wrapper.scale_factor());
 
-        let mut top_window_position = (0.0, 0.0);
-        let mut top_grid_position = None;
+        self.pointer_position = logical_position;
 
-        for details in self.renderer.window_regions.iter() {
-            if logical_position.width >= details.region.left as u32
-                && logical_position.width < details.region.right as u32
-                && logical_position.height >= details.region.top as u32
-                && logical_position.height < details.region.bottom as u32
-            {
-                top_window_position = (details.region.left, details.region.top);
-                top_grid_position = Some((
+        self.tab_under_mouse = self.renderer.tab_at(
+            logical_position.width as f32,
+            logical_position.height as f32,
+        );
+        if self.tab_under_mouse.is_some() || self.dragged_tab.is_some() {
+            return;
+        }
+
+        let top_region = self
+            .renderer
+            .window_region_at(logical_position.width, logical_position.height)
+            .map(|details| {
+                (
+                    (details.region.left, details.region.top),
                     details.id,
                     LogicalSize::new(
                         logical_position.width - details.region.left as u32,
                         logical_position.height - details.region.top as u32,
                     ),
                     details.floating,
-                ));
-            }
-        }
+                )
+            });
 
-        if let Some((grid_id, grid_position, grid_floating)) = top_grid_position {
+        if let Some((top_window_position, grid_id, grid_position, grid_floating)) = top_region {
             self.grid_id_under_mouse = grid_id;
             self.mouse_position = LogicalSize::new(
                 (grid_position.width as f32 / self.renderer.font_width) as u32,
                 (grid_position.height as f32 / self.renderer.font_height) as u32,
             );
 
-            if self.mouse_enabled && self.mouse_down && previous_position != self.mouse_position {
+            if self.mouse_enabled
+                && self.pressed_button.is_some()
+                && previous_position != self.mouse_position
+            {
                 let (window_left, window_top) = top_window_position;
 
                 // Until https://github.com/neovim/neovim/pull/12667 is merged, we have to special
@@ -210,43 +342,119 @@ impl Sdl2WindowWrapper {
                     (adjusted_drag_left, adjusted_drag_top)
                 };
 
-                self.ui_command_sender
-                    .send(UiCommand::Drag {
-                        grid_id: self.grid_id_under_mouse,
-                        position,
-                    })
-                    .ok();
+                self.send_drag(self.grid_id_under_mouse, position);
             }
         }
     }
 
-    pub fn handle_pointer_down(&mut self) {
+    pub fn handle_external_pointer_motion(&mut self, grid_id: u64, x: i32, y: i32) {
+        let previous_position = self.mouse_position;
+        let scale_factor = match self.external_windows.get(&grid_id) {
+            Some(external_window) => Sdl2Window::new(&external_window.window).scale_factor(),
+            None => return,
+        };
+        let logical_position = PhysicalSize::new(x as u32, y as u32).to_logical(scale_factor);
+
+        self.tab_under_mouse = None;
+        self.grid_id_under_mouse = grid_id;
+        self.mouse_position = LogicalSize::new(
+            (logical_position.width as f32 / self.renderer.font_width) as u32,
+            (logical_position.height as f32 / self.renderer.font_height) as u32,
+        );
+
+        if self.mouse_enabled
+            && self.pressed_button.is_some()
+            && previous_position != self.mouse_position
+        {
+            self.send_drag(
+                grid_id,
+                (self.mouse_position.width, self.mouse_position.height),
+            );
+        }
+    }
+
+    fn mouse_modifiers(&self) -> String {
+        Modifiers::from(self.context.keyboard().mod_state()).mouse_string()
+    }
+
+    fn send_drag(&self, grid_id: u64, position: (u32, u32)) {
+        if let Some(button) = self.pressed_button {
+            self.ui_command_sender
+                .send(UiCommand::Drag {
+                    button: button.to_string(),
+                    grid_id,
+                    position,
+                    modifiers: self.mouse_modifiers(),
+                })
+                .ok();
+        }
+    }
+
+    pub fn handle_pointer_down(&mut self, button: &'static str) {
+        if let Some(tab) = self.tab_under_mouse {
+            if button == "left" {
+                self.ui_command_sender.send(UiCommand::TabSelect(tab)).ok();
+                self.dragged_tab = Some(tab);
+            }
+            return;
+        }
+
         if self.mouse_enabled {
             self.ui_command_sender
                 .send(UiCommand::MouseButton {
+                    button: button.to_string(),
                     action: String::from("press"),
                     grid_id: self.grid_id_under_mouse,
                     position: (self.mouse_position.width, self.mouse_position.height),
+                    modifiers: self.mouse_modifiers(),
                 })
                 .ok();
         }
-        self.mouse_down = true;
+        self.pressed_button = Some(button);
     }
 
-    pub fn handle_pointer_up(&mut self) {
+    pub fn handle_pointer_up(&mut self, button: &'static str) {
+        if let Some(dragged_tab) = self.dragged_tab.take() {
+            if let Some(tab) = self.tab_under_mouse.filter(|tab| *tab != dragged_tab) {
+                self.ui_command_sender
+                    .send(UiCommand::TabMove {
+                        from: dragged_tab,
+                        to: tab,
+                    })
+                    .ok();
+            }
+            return;
+        }
+
         if self.mouse_enabled {
             self.ui_command_sender
                 .send(UiCommand::MouseButton {
+                    button: button.to_string(),
                     action: String::from("release"),
                     grid_id: self.grid_id_under_mouse,
                     position: (self.mouse_position.width, self.mouse_position.height),
+                    modifiers: self.mouse_modifiers(),
                 })
                 .ok();
         }
-        self.mouse_down = false;
+        if self.pressed_button == Some(button) {
+            self.pressed_button = None;
+        }
+    }
+
+    pub fn handle_tab_middle_click(&mut self) {
+        if let Some(tab) = self.tab_under_mouse {
+            self.ui_command_sender.send(UiCommand::TabClose(tab)).ok();
+        }
     }
 
     pub fn handle_mouse_wheel(&mut self, x: i32, y: i32) {
+        if self.renderer.message_history_visible() {
+            self.renderer.scroll_message_history(y as i64);
+            REDRAW_SCHEDULER.queue_next_frame();
+            return;
+        }
+
         if !self.mouse_enabled {
             return;
         }
@@ -263,11 +471,12 @@ impl Sdl2WindowWrapper {
                     direction: input_type.to_string(),
                     grid_id: self.grid_id_under_mouse,
                     position: (self.mouse_position.width, self.mouse_position.height),
+                    modifiers: self.mouse_modifiers(),
                 })
                 .ok();
         }
 
-        let horizontal_input_type = match y {
+        let horizontal_input_type = match x {
             _ if x > 0 => Some("right"),
             _ if x < 0 => Some("left"),
             _ => None,
@@ -279,11 +488,43 @@ impl Sdl2WindowWrapper {
                     direction: input_type.to_string(),
                     grid_id: self.grid_id_under_mouse,
                     position: (self.mouse_position.width, self.mouse_position.height),
+                    modifiers: self.mouse_modifiers(),
                 })
                 .ok();
         }
     }
 
+    pub fn handle_text_editing(&mut self, text: String, cursor: i32) {
+        let cursor = if cursor >= 0 {
+            Some(cursor as usize)
+        } else {
+            None
+        };
+        self.renderer.set_preedit(text, cursor);
+        REDRAW_SCHEDULER.queue_next_frame();
+    }
+
+    // Keeps the input method's candidate window next to the cursor.
+    fn update_ime_region(&mut self) {
+        let cursor_region = match self.renderer.cursor_region() {
+            Some(cursor_region) => cursor_region,
+            None => return,
+        };
+
+        let scale_factor = Sdl2Window::new(&self.window).scale_factor() as f32;
+        let ime_region = Rect::new(
+            (cursor_region.left * scale_factor) as i32,
+            (cursor_region.top * scale_factor) as i32,
+            (cursor_region.width() * scale_factor).max(1.0) as u32,
+            (cursor_region.height() * scale_factor).max(1.0) as u32,
+        );
+
+        if self.ime_region != Some(ime_region) {
+            self.window.subsystem().text_input().set_rect(ime_region);
+            self.ime_region = Some(ime_region);
+        }
+    }
+
     pub fn handle_focus_lost(&mut self) {
         self.ui_command_sender.send(UiCommand::FocusLost).ok();
     }
@@ -296,30 +537,60 @@ impl Sdl2WindowWrapper {
     fn handle_events(&mut self) {
         self.synchronize_settings();
 
-        let mut keycode = None;
-        let mut keytext = None;
+        let mut key_events = Vec::new();
         let mut ignore_text_this_frame = false;
 
         let window_events: Vec<Event> = self.event_pump.poll_iter().collect();
         for event in window_events.into_iter() {
             match event {
                 Event::Quit { .. } => self.handle_quit(),
-                Event::DropFile { filename, .. } => {
-                    self.ui_command_sender
-                        .send(UiCommand::FileDrop(filename))
-                        .ok();
+                Event::DropFile {
+                    window_id,
+                    filename,
+                    ..
+                } => self.handle_file_drop(window_id, filename),
+                Event::DropComplete { .. } => self.handle_drop_complete(),
+                // Despite the field name, dropped text arrives as the text itself
+                Event::DropText { filename, .. } => {
+                    self.ui_command_sender.send(UiCommand::Paste(filename)).ok();
                 }
                 Event::KeyDown {
-                    keycode: received_keycode,
+                    keycode: Some(keycode),
+                    keymod,
                     ..
-                } => {
-                    keycode = received_keycode;
+                } => key_events.push(KeyEvent::Down(keycode, Modifiers::from(keymod))),
+                Event::TextEditing { text, start, .. } => self.handle_text_editing(text, start),
+                Event::TextInput { text, .. } => {
+                    self.renderer.clear_preedit();
+                    let modifiers = Modifiers::from(self.context.keyboard().mod_state());
+                    key_events.push(KeyEvent::Text(text, modifiers));
+                }
+                Event::MouseMotion {
+                    window_id, x, y, ..
+                } => match self.external_grid_id(window_id) {
+                    Some(grid_id) => self.handle_external_pointer_motion(grid_id, x, y),
+                    None => self.handle_pointer_motion(x, y),
+                },
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Middle,
+                    ..
+                } if self.tab_under_mouse.is_some() => self.handle_tab_middle_click(),
+                Event::MouseButtonDown { mouse_btn, .. } => {
+                    if let Some(button) = mouse_button_name(mouse_btn) {
+                        self.handle_pointer_down(button);
+                    }
+                }
+                Event::MouseButtonUp { mouse_btn, .. } => {
+                    if let Some(button) = mouse_button_name(mouse_btn) {
+                        self.handle_pointer_up(button);
+                    }
                 }
-                Event::TextInput { text, .. } => keytext = Some(text),
-                Event::MouseMotion { x, y, .. } => self.handle_pointer_motion(x, y),
-                Event::MouseButtonDown { .. } => self.handle_pointer_down(),
-                Event::MouseButtonUp { .. } => self.handle_pointer_up(),
                 Event::MouseWheel { x, y, .. } => self.handle_mouse_wheel(x, y),
+                Event::Window {
+                    window_id,
+                    win_event: WindowEvent::Close,
+                    ..
+                } => self.handle_window_close(window_id),
                 Event::Window {
                     win_event: WindowEvent::FocusLost,
                     ..
@@ -336,8 +607,10 @@ impl Sdl2WindowWrapper {
             }
         }
 
-        if !ignore_text_this_frame {
-            self.handle_keyboard_input(keycode, keytext);
+        if ignore_text_this_frame {
+            self.key_composer.reset();
+        } else {
+            self.handle_keyboard_events(key_events);
         }
 
         let window_commands: Vec<WindowCommand> = self.window_command_receiver.try_iter().collect();
@@ -360,7 +633,10 @@ impl Sdl2WindowWrapper {
         let current_size = self.previous_size;
         let ui_command_sender = self.ui_command_sender.clone();
 
-        if REDRAW_SCHEDULER.should_draw() || SETTINGS.get::<WindowSettings>().no_idle {
+        let should_draw =
+            REDRAW_SCHEDULER.should_draw() || SETTINGS.get::<WindowSettings>().no_idle;
+
+        if should_draw {
             log::debug!("Render Triggered");
 
             let scaling = sdl_window_wrapper.scale_factor();
@@ -373,11 +649,115 @@ impl Sdl2WindowWrapper {
                     }
                 },
             )?;
+        }
 
-            Ok(true)
-        } else {
-            Ok(false)
+        if should_draw {
+            self.update_ime_region();
         }
+
+        // External windows are synchronized after the main frame so that windows created by
+        // this frame's draw commands show up immediately.
+        self.synchronize_external_windows();
+        self.draw_external_windows(dt, should_draw)?;
+
+        Ok(should_draw)
+    }
+
+    fn create_external_window(
+        &self,
+        details: &ExternalWindowDetails,
+    ) -> Result<Sdl2ExternalWindow, String> {
+        let window = self
+            .window
+            .subsystem()
+            .window(&self.title, details.width as u32, details.height as u32)
+            .allow_highdpi()
+            .resizable()
+            .build()
+            .map_err(|error| error.to_string())?;
+
+        let sdl_window_wrapper = Sdl2Window::new(&window);
+        let skulpin_renderer = external_window_renderer_builder()
+            .build(&sdl_window_wrapper)
+            .map_err(|error| format!("{:?}", error))?;
+        let previous_size = sdl_window_wrapper.logical_size();
+
+        Ok(Sdl2ExternalWindow {
+            window,
+            skulpin_renderer,
+            window_handle: details.window_handle,
+            previous_size,
+        })
+    }
+
+    fn synchronize_external_windows(&mut self) {
+        let external_window_details = self.renderer.external_windows();
+
+        self.external_windows.retain(|grid_id, _| {
+            external_window_details
+                .iter()
+                .any(|details| details.grid_id == *grid_id)
+        });
+
+        for details in external_window_details.iter() {
+            if self.external_windows.contains_key(&details.grid_id) {
+                continue;
+            }
+
+            match self.create_external_window(details) {
+                Ok(external_window) => {
+                    log::info!("external window created for grid {}", details.grid_id);
+                    self.external_windows
+                        .insert(details.grid_id, external_window);
+                    REDRAW_SCHEDULER.queue_next_frame();
+                }
+                Err(error) => log::error!("Could not create external window: {}", error),
+            }
+        }
+    }
+
+    fn draw_external_windows(&mut self, dt: f32, should_draw: bool) -> VkResult<()> {
+        for (grid_id, external_window) in self.external_windows.iter_mut() {
+            let sdl_window_wrapper = Sdl2Window::new(&external_window.window);
+            let new_size = sdl_window_wrapper.logical_size();
+            if external_window.previous_size != new_size {
+                handle_new_external_grid_size(
+                    *grid_id,
+                    new_size,
+                    &self.renderer,
+                    &self.ui_command_sender,
+                );
+                external_window.previous_size = new_size;
+            }
+
+            if should_draw {
+                let renderer = &mut self.renderer;
+                external_window.skulpin_renderer.draw(
+                    &sdl_window_wrapper,
+                    |canvas, coordinate_system_helper| {
+                        renderer.draw_external_window(
+                            *grid_id,
+                            canvas,
+                            &coordinate_system_helper,
+                            dt,
+                        );
+                    },
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn mouse_button_name(button: MouseButton) -> Option<&'static str> {
+    match button {
+        MouseButton::Left => Some("left"),
+        MouseButton::Right => Some("right"),
+        MouseButton::Middle => Some("middle"),
+        MouseButton::X1 => Some("x1"),
+        MouseButton::X2 => Some("x2"),
+        MouseButton::Unknown => None,
     }
 }
 
@@ -422,6 +802,8 @@ pub fn start_loop(
     renderer: Renderer,
 ) {
     sdl2::hint::set("SDL_MOUSE_FOCUS_CLICKTHROUGH", "1");
+    // Lets the input method draw its own candidate list on Windows
+    sdl2::hint::set("SDL_IME_SHOW_UI", "1");
 
     let context = sdl2::init().expect("Failed to initialize sdl2");
 
@@ -468,13 +850,21 @@ pub fn start_loop(
         skulpin_renderer,
         renderer,
         event_pump,
-        mouse_down: false,
+        pressed_button: None,
+        pointer_position: LogicalSize::new(0, 0),
+        dropped_files: Vec::new(),
+        drop_target: None,
+        ime_region: None,
+        key_composer: KeyComposer::new(true),
         mouse_position: LogicalSize {
             width: 0,
             height: 0,
         },
         mouse_enabled: true,
         grid_id_under_mouse: 0,
+        tab_under_mouse: None,
+        dragged_tab: None,
+        external_windows: HashMap::new(),
         title: String::from("Neovide"),
         previous_size: logical_size,
         transparency: 1.0,
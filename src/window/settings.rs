@@ -9,6 +9,8 @@ pub struct WindowSettings {
     pub no_idle: bool,
     pub fullscreen: bool,
     pub iso_layout: bool,
+    pub file_drop: FileDropMode,
+    pub scale_factor: f32,
 }
 
 impl Default for WindowSettings {
@@ -21,6 +23,55 @@ impl Default for WindowSettings {
                 .contains(&String::from("--noIdle")),
             fullscreen: false,
             iso_layout: false,
+            file_drop: FileDropMode::Drop,
+            scale_factor: 1.0,
+        }
+    }
+}
+
+// How files dropped onto the window are opened. Drop behaves like :drop, reusing a window which
+// already shows the file and replacing the argument list with the dropped files.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileDropMode {
+    Drop,
+    Split,
+    VerticalSplit,
+    Tab,
+}
+
+impl FileDropMode {
+    pub fn command(self) -> &'static str {
+        match self {
+            FileDropMode::Drop => "drop",
+            FileDropMode::Split => "split",
+            FileDropMode::VerticalSplit => "vsplit",
+            FileDropMode::Tab => "tabedit",
+        }
+    }
+}
+
+impl FromValue for FileDropMode {
+    fn from_value(&mut self, value: Value) {
+        match value.as_str() {
+            Some("drop") => *self = FileDropMode::Drop,
+            Some("split") => *self = FileDropMode::Split,
+            Some("vsplit") => *self = FileDropMode::VerticalSplit,
+            Some("tab") => *self = FileDropMode::Tab,
+            _ => log::error!(
+                "file_drop setting expected drop, split, vsplit or tab, but received: {}",
+                value
+            ),
+        }
+    }
+}
+
+impl From<FileDropMode> for Value {
+    fn from(mode: FileDropMode) -> Self {
+        match mode {
+            FileDropMode::Drop => "drop".into(),
+            FileDropMode::Split => "split".into(),
+            FileDropMode::VerticalSplit => "vsplit".into(),
+            FileDropMode::Tab => "tab".into(),
         }
     }
 }
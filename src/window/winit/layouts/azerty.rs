@@ -0,0 +1,55 @@
+use super::handle_qwerty_layout;
+use crate::window::keyboard::Token;
+use skulpin::winit::event::VirtualKeyCode::{self, *};
+
+/// Maps winit keyboard events on a French AZERTY layout to Vim tokens. Letters are reported by
+/// the character they type, but the number row and punctuation keys are reported by their
+/// position on a US keyboard.
+pub fn handle_azerty_layout(keycode: VirtualKeyCode, shift: bool) -> Option<Token<'static>> {
+    let special = |text| Some(Token::new(text, true, true));
+    let partial = |text| Some(Token::new(text, false, false));
+    match (keycode, shift) {
+        (Key1, false) => partial("&"),
+        (Key1, true) => partial("1"),
+        (Key2, false) => partial("é"),
+        (Key2, true) => partial("2"),
+        (Key3, false) => partial("\""),
+        (Key3, true) => partial("3"),
+        (Key4, false) => partial("'"),
+        (Key4, true) => partial("4"),
+        (Key5, false) => partial("("),
+        (Key5, true) => partial("5"),
+        (Key6, false) => partial("-"),
+        (Key6, true) => partial("6"),
+        (Key7, false) => partial("è"),
+        (Key7, true) => partial("7"),
+        (Key8, false) => partial("_"),
+        (Key8, true) => partial("8"),
+        (Key9, false) => partial("ç"),
+        (Key9, true) => partial("9"),
+        (Key0, false) => partial("à"),
+        (Key0, true) => partial("0"),
+        (LBracket, false) => partial(")"),
+        (LBracket, true) => partial("°"),
+        (Equals, false) => partial("="),
+        (Equals, true) => partial("+"),
+        (RBracket, false) => partial("^"),
+        (RBracket, true) => partial("¨"),
+        (Semicolon, false) => partial("$"),
+        (Semicolon, true) => partial("£"),
+        (Grave, false) => partial("ù"),
+        (Grave, true) => partial("%"),
+        (Backslash, false) => partial("*"),
+        (Backslash, true) => partial("µ"),
+        (Comma, false) => partial(","),
+        (Comma, true) => partial("?"),
+        (Period, false) => partial(";"),
+        (Period, true) => partial("."),
+        (Slash, false) => partial(":"),
+        (Slash, true) => partial("/"),
+        (OEM102, false) => special("lt"),
+        (OEM102, true) => partial(">"),
+        (Apostrophe, _) => partial("²"),
+        (keycode, shift) => handle_qwerty_layout(keycode, shift),
+    }
+}
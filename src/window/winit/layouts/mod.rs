@@ -1,9 +1,29 @@
+mod azerty;
 mod qwerty;
+mod qwertz;
 
-use crate::window::keyboard::Modifiers;
-use skulpin::winit::event::ModifiersState;
+use crate::window::keyboard::{KeyboardLayout, Modifiers, Token};
+use skulpin::winit::event::{ModifiersState, VirtualKeyCode};
 
-pub use qwerty::handle_qwerty_layout;
+use azerty::handle_azerty_layout;
+use qwerty::handle_qwerty_layout;
+use qwertz::handle_qwertz_layout;
+
+pub fn handle_keyboard_layout(
+    layout: &KeyboardLayout,
+    keycode: VirtualKeyCode,
+    shift: bool,
+) -> Option<Token<'static>> {
+    match layout {
+        KeyboardLayout::Azerty => handle_azerty_layout(keycode, shift),
+        KeyboardLayout::Qwertz => handle_qwertz_layout(keycode, shift),
+        // Punctuation keys are reported by the symbol they type on a Dvorak layout, and US
+        // Dvorak pairs every symbol with the same shifted symbol as US QWERTY.
+        KeyboardLayout::Qwerty | KeyboardLayout::Dvorak | KeyboardLayout::Agnostic => {
+            handle_qwerty_layout(keycode, shift)
+        }
+    }
+}
 
 impl From<Option<ModifiersState>> for Modifiers {
     fn from(state: Option<ModifiersState>) -> Modifiers {
@@ -0,0 +1,46 @@
+use super::handle_qwerty_layout;
+use crate::window::keyboard::Token;
+use skulpin::winit::event::VirtualKeyCode::{self, *};
+
+/// Maps winit keyboard events on a German QWERTZ layout to Vim tokens. Letters are reported by
+/// the character they type, but the number row and punctuation keys are reported by their
+/// position on a US keyboard.
+pub fn handle_qwertz_layout(keycode: VirtualKeyCode, shift: bool) -> Option<Token<'static>> {
+    let special = |text| Some(Token::new(text, true, true));
+    let partial = |text| Some(Token::new(text, false, false));
+    match (keycode, shift) {
+        (Key1, true) => partial("!"),
+        (Key2, true) => partial("\""),
+        (Key3, true) => partial("§"),
+        (Key4, true) => partial("$"),
+        (Key5, true) => partial("%"),
+        (Key6, true) => partial("&"),
+        (Key7, true) => partial("/"),
+        (Key8, true) => partial("("),
+        (Key9, true) => partial(")"),
+        (Key0, true) => partial("="),
+        (LBracket, false) => partial("ß"),
+        (LBracket, true) => partial("?"),
+        (Semicolon, false) => partial("ü"),
+        (Semicolon, true) => partial("Ü"),
+        (Grave, false) => partial("ö"),
+        (Grave, true) => partial("Ö"),
+        (Apostrophe, false) => partial("ä"),
+        (Apostrophe, true) => partial("Ä"),
+        (Equals, false) => partial("+"),
+        (Equals, true) => partial("*"),
+        (Slash, false) => partial("#"),
+        (Slash, true) => partial("'"),
+        (Minus, false) => partial("-"),
+        (Minus, true) => partial("_"),
+        (Period, false) => partial("."),
+        (Period, true) => partial(":"),
+        (Comma, false) => partial(","),
+        (Comma, true) => partial(";"),
+        (Backslash, false) => partial("^"),
+        (Backslash, true) => partial("°"),
+        (OEM102, false) => special("lt"),
+        (OEM102, true) => partial(">"),
+        (keycode, shift) => handle_qwerty_layout(keycode, shift),
+    }
+}
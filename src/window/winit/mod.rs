@@ -1,14 +1,26 @@
 #[macro_use]
 mod layouts;
 
-use super::{handle_new_grid_size, keyboard::neovim_keybinding_string, settings::WindowSettings};
+use super::{
+    external_window_renderer_builder, handle_new_external_grid_size, handle_new_grid_size,
+    handle_paste, handle_zoom,
+    keyboard::{
+        expects_text, neovim_keybinding_string, GuiAction, KeyComposer, KeyEvent, KeyboardSettings,
+        Modifiers,
+    },
+    settings::WindowSettings,
+};
 use crate::{
-    bridge::UiCommand, editor::WindowCommand, error_handling::ResultPanicExplanation,
-    redraw_scheduler::REDRAW_SCHEDULER, renderer::Renderer, settings::SETTINGS,
+    bridge::UiCommand,
+    editor::WindowCommand,
+    error_handling::ResultPanicExplanation,
+    redraw_scheduler::REDRAW_SCHEDULER,
+    renderer::{ExternalWindowDetails, Renderer},
+    settings::SETTINGS,
 };
 use crossfire::mpsc::TxUnbounded;
 use image::{load_from_memory, GenericImageView, Pixel};
-use layouts::handle_qwerty_layout;
+use layouts::handle_keyboard_layout;
 use skulpin::{
     ash::prelude::VkResult,
     winit::{
@@ -17,13 +29,14 @@ use skulpin::{
             ElementState, Event, ModifiersState, MouseButton, MouseScrollDelta,
             VirtualKeyCode as Keycode, WindowEvent,
         },
-        event_loop::{ControlFlow, EventLoop},
-        window::{Fullscreen, Icon},
+        event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget},
+        window::{Fullscreen, Icon, WindowId},
     },
     CoordinateSystem, LogicalSize, PhysicalSize, PresentMode, Renderer as SkulpinRenderer,
     RendererBuilder, Window, WinitWindow,
 };
 use std::{
+    collections::HashMap,
     sync::{
         atomic::{AtomicBool, Ordering},
         mpsc::Receiver,
@@ -36,15 +49,31 @@ use std::{
 #[folder = "assets/"]
 struct Asset;
 
+// An os window holding a single grid which neovim has moved out of the main window.
+struct WinitExternalWindow {
+    window: winit::window::Window,
+    skulpin_renderer: SkulpinRenderer,
+    window_handle: u64,
+    previous_size: LogicalSize,
+}
+
 pub struct WinitWindowWrapper {
     window: winit::window::Window,
     skulpin_renderer: SkulpinRenderer,
     renderer: Renderer,
-    mouse_down: bool,
+    pressed_button: Option<&'static str>,
+    pointer_position: LogicalSize,
+    dropped_files: Vec<String>,
+    drop_target: Option<u64>,
+    ime_position: Option<(i32, i32)>,
     mouse_position: LogicalSize,
     mouse_enabled: bool,
     grid_id_under_mouse: u64,
+    tab_under_mouse: Option<u64>,
+    dragged_tab: Option<u64>,
+    external_windows: HashMap<u64, WinitExternalWindow>,
     current_modifiers: Option<ModifiersState>,
+    key_composer: KeyComposer<Keycode>,
     title: String,
     previous_size: LogicalSize,
     fullscreen: bool,
@@ -97,26 +126,108 @@ impl WinitWindowWrapper {
         self.window.set_title(&self.title);
     }
 
+    // The bridge decides how to shut down since remote sessions should only be detached from
     pub fn handle_quit(&mut self) {
-        self.running.store(false, Ordering::Relaxed);
+        if self.ui_command_sender.send(UiCommand::Quit).is_err() {
+            self.running.store(false, Ordering::Relaxed);
+        }
     }
 
-    pub fn handle_keyboard_input(
-        &mut self,
-        keycode: Option<Keycode>,
-        modifiers: Option<ModifiersState>,
-    ) {
-        if keycode.is_some() {
-            log::trace!(
-                "Keyboard Input Received: keycode-{:?} modifiers-{:?} ",
-                keycode,
-                modifiers
-            );
+    fn external_grid_id(&self, window_id: WindowId) -> Option<u64> {
+        self.external_windows
+            .iter()
+            .find(|(_, external_window)| external_window.window.id() == window_id)
+            .map(|(grid_id, _)| *grid_id)
+    }
+
+    // Closing the main window quits, but closing an external window only closes the neovim
+    // window shown in it.
+    pub fn handle_window_close(&mut self, window_id: WindowId) {
+        if let Some(grid_id) = self.external_grid_id(window_id) {
+            let window_handle = self.external_windows[&grid_id].window_handle;
+            self.ui_command_sender
+                .send(UiCommand::CloseWindow(window_handle))
+                .ok();
+        } else if window_id == self.window.id() {
+            self.handle_quit();
+        }
+    }
+
+    // The os doesn't say where files were dropped, so the last known pointer position is hit
+    // tested against the window regions to find the split they were dropped onto.
+    pub fn handle_file_drop(&mut self, window_id: WindowId, path: String) {
+        if self.dropped_files.is_empty() {
+            self.drop_target = match self.external_grid_id(window_id) {
+                Some(grid_id) => Some(self.external_windows[&grid_id].window_handle),
+                None => self
+                    .renderer
+                    .window_region_at(self.pointer_position.width, self.pointer_position.height)
+                    .and_then(|details| details.window_handle),
+            };
+        }
+        self.dropped_files.push(path);
+    }
+
+    // Each dropped file arrives as its own event, so they are collected and opened together once
+    // the drop is complete.
+    pub fn handle_drop_complete(&mut self) {
+        if self.dropped_files.is_empty() {
+            return;
         }
 
-        if let Some(keybinding_string) =
-            neovim_keybinding_string(keycode, None, modifiers, handle_qwerty_layout)
+        self.ui_command_sender
+            .send(UiCommand::FileDrop {
+                paths: std::mem::take(&mut self.dropped_files),
+                window_handle: self.drop_target.take(),
+                mode: SETTINGS.get::<WindowSettings>().file_drop,
+            })
+            .ok();
+    }
+
+    pub fn handle_keyboard_input(&mut self, event: KeyEvent<Keycode>) {
+        log::trace!("Keyboard Input Received: {:?}", event);
+
+        let layout = SETTINGS.get::<KeyboardSettings>().keyboard_layout;
+        for input in self
+            .key_composer
+            .compose(event, &layout, handle_keyboard_layout)
+            .into_iter()
         {
+            self.send_keyboard_input(input);
+        }
+    }
+
+    pub fn flush_keyboard_input(&mut self) {
+        for input in self.key_composer.flush().into_iter() {
+            self.send_keyboard_input(input);
+        }
+    }
+
+    fn send_keyboard_input(&mut self, input: KeyEvent<Keycode>) {
+        let keybinding_string = match input {
+            KeyEvent::Down(keycode, modifiers) => {
+                neovim_keybinding_string(Some(keycode), None, modifiers, handle_keyboard_layout)
+            }
+            KeyEvent::Text(text, modifiers) => {
+                neovim_keybinding_string(None, Some(text), modifiers, handle_keyboard_layout)
+            }
+        };
+
+        if let Some(keybinding_string) = keybinding_string {
+            let keymap = SETTINGS.get::<KeyboardSettings>().keymap;
+            if let Some(action) = keymap.action(&keybinding_string) {
+                self.handle_gui_action(action);
+                return;
+            }
+
+            // Any key press dismisses the message history the same way it would in the terminal,
+            // and like the more-prompt the key is not passed on to neovim
+            if self.renderer.message_history_visible() {
+                self.renderer.hide_message_history();
+                REDRAW_SCHEDULER.queue_next_frame();
+                return;
+            }
+
             self.ui_command_sender
                 .send(UiCommand::Keyboard(keybinding_string))
                 .unwrap_or_explained_panic(
@@ -125,41 +236,62 @@ impl WinitWindowWrapper {
         }
     }
 
+    pub fn handle_gui_action(&mut self, action: GuiAction) {
+        log::trace!("Gui Action: {:?}", action);
+        match action {
+            GuiAction::ToggleFullscreen => self.toggle_fullscreen(),
+            GuiAction::Copy => {
+                self.ui_command_sender.send(UiCommand::Copy).ok();
+            }
+            GuiAction::Paste => handle_paste(&self.ui_command_sender),
+            GuiAction::ZoomIn | GuiAction::ZoomOut | GuiAction::ZoomReset => {
+                handle_zoom(action, &self.ui_command_sender)
+            }
+        }
+    }
+
     pub fn handle_pointer_motion(&mut self, x: i32, y: i32) {
         let previous_position = self.mouse_position;
         let winit_window_wrapper = WinitWindow::new(&self.window);
         let logical_position =
             PhysicalSize::new(x as u32, y as u32).to_logical(winit_window_wrapper.scale_factor());
 
-        let mut top_window_position = (0.0, 0.0);
-        let mut top_grid_position = None;
+        self.pointer_position = logical_position;
 
-        for details in self.renderer.window_regions.iter() {
-            if logical_position.width >= details.region.left as u32
-                && logical_position.width < details.region.right as u32
-                && logical_position.height >= details.region.top as u32
-                && logical_position.height < details.region.bottom as u32
-            {
-                top_window_position = (details.region.left, details.region.top);
-                top_grid_position = Some((
+        self.tab_under_mouse = self.renderer.tab_at(
+            logical_position.width as f32,
+            logical_position.height as f32,
+        );
+        if self.tab_under_mouse.is_some() || self.dragged_tab.is_some() {
+            return;
+        }
+
+        let top_region = self
+            .renderer
+            .window_region_at(logical_position.width, logical_position.height)
+            .map(|details| {
+                (
+                    (details.region.left, details.region.top),
                     details.id,
                     LogicalSize::new(
                         logical_position.width - details.region.left as u32,
                         logical_position.height - details.region.top as u32,
                     ),
                     details.floating,
-                ));
-            }
-        }
+                )
+            });
 
-        if let Some((grid_id, grid_position, grid_floating)) = top_grid_position {
+        if let Some((top_window_position, grid_id, grid_position, grid_floating)) = top_region {
             self.grid_id_under_mouse = grid_id;
             self.mouse_position = LogicalSize::new(
                 (grid_position.width as f32 / self.renderer.font_width) as u32,
                 (grid_position.height as f32 / self.renderer.font_height) as u32,
             );
 
-            if self.mouse_enabled && self.mouse_down && previous_position != self.mouse_position {
+            if self.mouse_enabled
+                && self.pressed_button.is_some()
+                && previous_position != self.mouse_position
+            {
                 let (window_left, window_top) = top_window_position;
 
                 // Until https://github.com/neovim/neovim/pull/12667 is merged, we have to special
@@ -175,43 +307,119 @@ impl WinitWindowWrapper {
                     (adjusted_drag_left, adjusted_drag_top)
                 };
 
-                self.ui_command_sender
-                    .send(UiCommand::Drag {
-                        grid_id: self.grid_id_under_mouse,
-                        position,
-                    })
-                    .ok();
+                self.send_drag(self.grid_id_under_mouse, position);
             }
         }
     }
 
-    pub fn handle_pointer_down(&mut self) {
+    pub fn handle_external_pointer_motion(&mut self, grid_id: u64, x: i32, y: i32) {
+        let previous_position = self.mouse_position;
+        let scale_factor = match self.external_windows.get(&grid_id) {
+            Some(external_window) => WinitWindow::new(&external_window.window).scale_factor(),
+            None => return,
+        };
+        let logical_position = PhysicalSize::new(x as u32, y as u32).to_logical(scale_factor);
+
+        self.tab_under_mouse = None;
+        self.grid_id_under_mouse = grid_id;
+        self.mouse_position = LogicalSize::new(
+            (logical_position.width as f32 / self.renderer.font_width) as u32,
+            (logical_position.height as f32 / self.renderer.font_height) as u32,
+        );
+
+        if self.mouse_enabled
+            && self.pressed_button.is_some()
+            && previous_position != self.mouse_position
+        {
+            self.send_drag(
+                grid_id,
+                (self.mouse_position.width, self.mouse_position.height),
+            );
+        }
+    }
+
+    fn mouse_modifiers(&self) -> String {
+        Modifiers::from(self.current_modifiers).mouse_string()
+    }
+
+    fn send_drag(&self, grid_id: u64, position: (u32, u32)) {
+        if let Some(button) = self.pressed_button {
+            self.ui_command_sender
+                .send(UiCommand::Drag {
+                    button: button.to_string(),
+                    grid_id,
+                    position,
+                    modifiers: self.mouse_modifiers(),
+                })
+                .ok();
+        }
+    }
+
+    pub fn handle_pointer_down(&mut self, button: &'static str) {
+        if let Some(tab) = self.tab_under_mouse {
+            if button == "left" {
+                self.ui_command_sender.send(UiCommand::TabSelect(tab)).ok();
+                self.dragged_tab = Some(tab);
+            }
+            return;
+        }
+
         if self.mouse_enabled {
             self.ui_command_sender
                 .send(UiCommand::MouseButton {
+                    button: button.to_string(),
                     action: String::from("press"),
                     grid_id: self.grid_id_under_mouse,
                     position: (self.mouse_position.width, self.mouse_position.height),
+                    modifiers: self.mouse_modifiers(),
                 })
                 .ok();
         }
-        self.mouse_down = true;
+        self.pressed_button = Some(button);
     }
 
-    pub fn handle_pointer_up(&mut self) {
+    pub fn handle_pointer_up(&mut self, button: &'static str) {
+        if let Some(dragged_tab) = self.dragged_tab.take() {
+            if let Some(tab) = self.tab_under_mouse.filter(|tab| *tab != dragged_tab) {
+                self.ui_command_sender
+                    .send(UiCommand::TabMove {
+                        from: dragged_tab,
+                        to: tab,
+                    })
+                    .ok();
+            }
+            return;
+        }
+
         if self.mouse_enabled {
             self.ui_command_sender
                 .send(UiCommand::MouseButton {
+                    button: button.to_string(),
                     action: String::from("release"),
                     grid_id: self.grid_id_under_mouse,
                     position: (self.mouse_position.width, self.mouse_position.height),
+                    modifiers: self.mouse_modifiers(),
                 })
                 .ok();
         }
-        self.mouse_down = false;
+        if self.pressed_button == Some(button) {
+            self.pressed_button = None;
+        }
+    }
+
+    pub fn handle_tab_middle_click(&mut self) {
+        if let Some(tab) = self.tab_under_mouse {
+            self.ui_command_sender.send(UiCommand::TabClose(tab)).ok();
+        }
     }
 
     pub fn handle_mouse_wheel(&mut self, x: i32, y: i32) {
+        if self.renderer.message_history_visible() {
+            self.renderer.scroll_message_history(y as i64);
+            REDRAW_SCHEDULER.queue_next_frame();
+            return;
+        }
+
         if !self.mouse_enabled {
             return;
         }
@@ -228,11 +436,12 @@ impl WinitWindowWrapper {
                     direction: input_type.to_string(),
                     grid_id: self.grid_id_under_mouse,
                     position: (self.mouse_position.width, self.mouse_position.height),
+                    modifiers: self.mouse_modifiers(),
                 })
                 .ok();
         }
 
-        let horizontal_input_type = match y {
+        let horizontal_input_type = match x {
             _ if x > 0 => Some("right"),
             _ if x < 0 => Some("left"),
             _ => None,
@@ -244,11 +453,31 @@ impl WinitWindowWrapper {
                     direction: input_type.to_string(),
                     grid_id: self.grid_id_under_mouse,
                     position: (self.mouse_position.width, self.mouse_position.height),
+                    modifiers: self.mouse_modifiers(),
                 })
                 .ok();
         }
     }
 
+    // Keeps the input method's candidate window just below the cursor. Winit doesn't report the
+    // text being composed, so there is no preedit to draw with this backend.
+    fn update_ime_position(&mut self) {
+        let cursor_region = match self.renderer.cursor_region() {
+            Some(cursor_region) => cursor_region,
+            None => return,
+        };
+
+        let ime_position = (cursor_region.left as i32, cursor_region.bottom as i32);
+        if self.ime_position != Some(ime_position) {
+            self.window
+                .set_ime_position(winit::dpi::LogicalPosition::new(
+                    ime_position.0,
+                    ime_position.1,
+                ));
+            self.ime_position = Some(ime_position);
+        }
+    }
+
     pub fn handle_focus_lost(&mut self) {
         self.ui_command_sender.send(UiCommand::FocusLost).ok();
     }
@@ -259,7 +488,7 @@ impl WinitWindowWrapper {
     }
 
     pub fn handle_event(&mut self, event: Event<()>) {
-        let mut keycode = None;
+        let mut key_event = None;
         let mut ignore_text_this_frame = false;
 
         match event {
@@ -267,27 +496,39 @@ impl WinitWindowWrapper {
                 self.handle_quit();
             }
             Event::WindowEvent {
+                window_id,
                 event: WindowEvent::CloseRequested,
-                ..
             } => {
-                self.handle_quit();
+                self.handle_window_close(window_id);
             }
             Event::WindowEvent {
+                window_id,
                 event: WindowEvent::DroppedFile(path),
-                ..
-            } => {
-                self.ui_command_sender
-                    .send(UiCommand::FileDrop(
-                        path.into_os_string().into_string().unwrap(),
-                    ))
-                    .ok();
+            } => self.handle_file_drop(window_id, path.to_string_lossy().into_owned()),
+            Event::MainEventsCleared => {
+                self.handle_drop_complete();
+                self.flush_keyboard_input();
             }
             Event::WindowEvent {
                 event: WindowEvent::KeyboardInput { input, .. },
                 ..
             } => {
-                if input.state == ElementState::Pressed {
-                    keycode = input.virtual_keycode;
+                if let (ElementState::Pressed, Some(keycode)) = (input.state, input.virtual_keycode)
+                {
+                    let modifiers = Modifiers::from(self.current_modifiers);
+                    key_event = Some(KeyEvent::Down(keycode, modifiers));
+                }
+            }
+            // Control characters and keys held with modifiers which don't type text are sent from
+            // their key presses instead.
+            Event::WindowEvent {
+                event: WindowEvent::ReceivedCharacter(character),
+                ..
+            } if !character.is_control() => {
+                let modifiers = Modifiers::from(self.current_modifiers);
+                let layout = SETTINGS.get::<KeyboardSettings>().keyboard_layout;
+                if expects_text(&layout, modifiers) {
+                    key_event = Some(KeyEvent::Text(character.to_string(), modifiers));
                 }
             }
             Event::WindowEvent {
@@ -297,9 +538,16 @@ impl WinitWindowWrapper {
                 self.current_modifiers = Some(m);
             }
             Event::WindowEvent {
+                window_id,
                 event: WindowEvent::CursorMoved { position, .. },
-                ..
-            } => self.handle_pointer_motion(position.x as i32, position.y as i32),
+            } => match self.external_grid_id(window_id) {
+                Some(grid_id) => self.handle_external_pointer_motion(
+                    grid_id,
+                    position.x as i32,
+                    position.y as i32,
+                ),
+                None => self.handle_pointer_motion(position.x as i32, position.y as i32),
+            },
             Event::WindowEvent {
                 event:
                     WindowEvent::MouseWheel {
@@ -312,16 +560,22 @@ impl WinitWindowWrapper {
             Event::WindowEvent {
                 event:
                     WindowEvent::MouseInput {
-                        button: MouseButton::Left,
-                        state,
+                        button: MouseButton::Middle,
+                        state: ElementState::Pressed,
                         ..
                     },
                 ..
+            } if self.tab_under_mouse.is_some() => self.handle_tab_middle_click(),
+            Event::WindowEvent {
+                event: WindowEvent::MouseInput { button, state, .. },
+                ..
             } => {
-                if state == ElementState::Pressed {
-                    self.handle_pointer_down();
-                } else {
-                    self.handle_pointer_up();
+                if let Some(button) = mouse_button_name(button) {
+                    if state == ElementState::Pressed {
+                        self.handle_pointer_down(button);
+                    } else {
+                        self.handle_pointer_up(button);
+                    }
                 }
             }
             Event::WindowEvent {
@@ -339,12 +593,18 @@ impl WinitWindowWrapper {
             _ => {}
         }
 
-        if !ignore_text_this_frame {
-            self.handle_keyboard_input(keycode, self.current_modifiers);
+        if ignore_text_this_frame {
+            self.key_composer.reset();
+        } else if let Some(key_event) = key_event {
+            self.handle_keyboard_input(key_event);
         }
     }
 
-    pub fn draw_frame(&mut self, dt: f32) -> VkResult<bool> {
+    pub fn draw_frame(
+        &mut self,
+        dt: f32,
+        window_target: &EventLoopWindowTarget<()>,
+    ) -> VkResult<bool> {
         let winit_window_wrapper = WinitWindow::new(&self.window);
         let new_size = winit_window_wrapper.logical_size();
         if self.previous_size != new_size {
@@ -355,7 +615,10 @@ impl WinitWindowWrapper {
         let current_size = self.previous_size;
         let ui_command_sender = self.ui_command_sender.clone();
 
-        if REDRAW_SCHEDULER.should_draw() || SETTINGS.get::<WindowSettings>().no_idle {
+        let should_draw =
+            REDRAW_SCHEDULER.should_draw() || SETTINGS.get::<WindowSettings>().no_idle;
+
+        if should_draw {
             log::debug!("Render Triggered");
 
             let scaling = winit_window_wrapper.scale_factor();
@@ -368,11 +631,118 @@ impl WinitWindowWrapper {
                     }
                 },
             )?;
+        }
 
-            Ok(true)
-        } else {
-            Ok(false)
+        if should_draw {
+            self.update_ime_position();
         }
+
+        // External windows are synchronized after the main frame so that windows created by
+        // this frame's draw commands show up immediately.
+        self.synchronize_external_windows(window_target);
+        self.draw_external_windows(dt, should_draw)?;
+
+        Ok(should_draw)
+    }
+
+    fn create_external_window(
+        &self,
+        details: &ExternalWindowDetails,
+        window_target: &EventLoopWindowTarget<()>,
+    ) -> Result<WinitExternalWindow, String> {
+        let window = winit::window::WindowBuilder::new()
+            .with_title(&self.title)
+            .with_inner_size(winit::dpi::LogicalSize::new(
+                details.width as u32,
+                details.height as u32,
+            ))
+            .build(window_target)
+            .map_err(|error| error.to_string())?;
+
+        let winit_window_wrapper = WinitWindow::new(&window);
+        let skulpin_renderer = external_window_renderer_builder()
+            .build(&winit_window_wrapper)
+            .map_err(|error| format!("{:?}", error))?;
+        let previous_size = winit_window_wrapper.logical_size();
+
+        Ok(WinitExternalWindow {
+            window,
+            skulpin_renderer,
+            window_handle: details.window_handle,
+            previous_size,
+        })
+    }
+
+    fn synchronize_external_windows(&mut self, window_target: &EventLoopWindowTarget<()>) {
+        let external_window_details = self.renderer.external_windows();
+
+        self.external_windows.retain(|grid_id, _| {
+            external_window_details
+                .iter()
+                .any(|details| details.grid_id == *grid_id)
+        });
+
+        for details in external_window_details.iter() {
+            if self.external_windows.contains_key(&details.grid_id) {
+                continue;
+            }
+
+            match self.create_external_window(details, window_target) {
+                Ok(external_window) => {
+                    log::info!("external window created for grid {}", details.grid_id);
+                    self.external_windows
+                        .insert(details.grid_id, external_window);
+                    REDRAW_SCHEDULER.queue_next_frame();
+                }
+                Err(error) => log::error!("Could not create external window: {}", error),
+            }
+        }
+    }
+
+    fn draw_external_windows(&mut self, dt: f32, should_draw: bool) -> VkResult<()> {
+        for (grid_id, external_window) in self.external_windows.iter_mut() {
+            let winit_window_wrapper = WinitWindow::new(&external_window.window);
+            let new_size = winit_window_wrapper.logical_size();
+            if external_window.previous_size != new_size {
+                handle_new_external_grid_size(
+                    *grid_id,
+                    new_size,
+                    &self.renderer,
+                    &self.ui_command_sender,
+                );
+                external_window.previous_size = new_size;
+            }
+
+            if should_draw {
+                let renderer = &mut self.renderer;
+                external_window.skulpin_renderer.draw(
+                    &winit_window_wrapper,
+                    |canvas, coordinate_system_helper| {
+                        renderer.draw_external_window(
+                            *grid_id,
+                            canvas,
+                            &coordinate_system_helper,
+                            dt,
+                        );
+                    },
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Winit reports the side buttons as raw platform button numbers, which differ between X11
+// (8 and 9) and Windows/macOS (1 and 2).
+fn mouse_button_name(button: MouseButton) -> Option<&'static str> {
+    match button {
+        MouseButton::Left => Some("left"),
+        MouseButton::Right => Some("right"),
+        MouseButton::Middle => Some("middle"),
+        MouseButton::Other(1) | MouseButton::Other(8) => Some("x1"),
+        MouseButton::Other(2) | MouseButton::Other(9) => Some("x2"),
+        MouseButton::Other(_) => None,
     }
 }
 
@@ -423,14 +793,22 @@ pub fn start_loop(
         window: winit_window,
         skulpin_renderer,
         renderer,
-        mouse_down: false,
+        pressed_button: None,
+        pointer_position: LogicalSize::new(0, 0),
+        dropped_files: Vec::new(),
+        drop_target: None,
+        ime_position: None,
         mouse_position: LogicalSize {
             width: 0,
             height: 0,
         },
         mouse_enabled: true,
         grid_id_under_mouse: 0,
+        tab_under_mouse: None,
+        dragged_tab: None,
+        external_windows: HashMap::new(),
         current_modifiers: None,
+        key_composer: KeyComposer::new(true),
         title: String::from("Neovide"),
         previous_size: logical_size,
         fullscreen: false,
@@ -444,7 +822,7 @@ pub fn start_loop(
     let mut was_animating = false;
     let previous_frame_start = Instant::now();
 
-    event_loop.run(move |e, _window_target, control_flow| {
+    event_loop.run(move |e, window_target, control_flow| {
         if !running.load(Ordering::Relaxed) {
             *control_flow = ControlFlow::Exit;
             return;
@@ -476,7 +854,7 @@ pub fn start_loop(
             }
         }
 
-        match window_wrapper.draw_frame(dt) {
+        match window_wrapper.draw_frame(dt, window_target) {
             Ok(animating) => {
                 was_animating = animating;
             }